@@ -0,0 +1,52 @@
+/** time how long it takes to import a large register.
+ * A TSV file with 50,000 entries is imported into a new database,
+ * then imported again, so that both adding and updating entries are measured.
+ *
+ * Run with cargo bench.
+ */
+use std::{ env, fs, process::{ Command, Stdio }, time::Instant };
+
+const ENTRY_COUNT: usize = 50_000;
+
+fn main() {
+    let directory = env::temp_dir().join(format!("rcheckbook-bench-{}", std::process::id()));
+    fs::create_dir_all(&directory).expect("could not create benchmark directory");
+
+    let database = directory.join("register.db");
+    let input = directory.join("entries.tsv");
+
+    fs::write(&input, entries()).expect("could not write entries");
+
+    for pass in ["add", "update"] {
+        let start = Instant::now();
+
+        let status = Command::new(env!("CARGO_BIN_EXE_rcheckbook"))
+        .arg("import")
+        .arg(&database)
+        .arg("-i")
+        .arg(&input)
+        .stdout(Stdio::null())
+        .status()
+        .expect("could not run rcheckbook");
+
+        assert!(status.success(), "import failed with {}", status);
+
+        println!("import {} entries ({})\t{:.2?}", ENTRY_COUNT, pass, start.elapsed());
+    }
+
+    fs::remove_dir_all(&directory).expect("could not remove benchmark directory");
+}
+
+/// create entries in the same layout as a TSV export.
+fn entries() -> String {
+    (0..ENTRY_COUNT).map(|index| {
+        let date = format!("2024-{:02}-{:02}", index % 12 + 1, index % 28 + 1);
+        let (credit, withdrawal) = if index % 5 == 0 {
+            (format!("{}.{:02}", index % 1000, index % 100), String::default())
+        } else {
+            (String::default(), format!("{}.{:02}", index % 100, index % 100))
+        };
+
+        format!("BENCH-{:05}\t{}\t\tN\tGroceries\tVendor {}\tEntry {}\t{}\t{}\n", index, date, index % 50, index, credit, withdrawal)
+    }).collect()
+}
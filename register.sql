@@ -1,8 +1,16 @@
 BEGIN TRANSACTION;
+DROP TABLE IF EXISTS "accounts";
+CREATE TABLE "accounts" (
+	"id"	INTEGER,
+	"name"	TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	"closed"	INTEGER NOT NULL DEFAULT 0 CHECK("closed" IN (0, 1)),
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
 DROP TABLE IF EXISTS "categories";
 CREATE TABLE "categories" (
 	"id"	INTEGER,
 	"category"	TEXT NOT NULL,
+	"parent"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
 	PRIMARY KEY("id" AUTOINCREMENT)
 ) STRICT;
 DROP TABLE IF EXISTS "trades";
@@ -12,26 +20,103 @@ CREATE TABLE "trades" (
 	"check_number"	INTEGER DEFAULT NULL,
 	"vendor"	TEXT DEFAULT '',
 	"memo"	TEXT DEFAULT '',
-	"amount"	REAL NOT NULL DEFAULT 0.0,
+	"amount"	INTEGER NOT NULL DEFAULT 0,
 	"category"	INTEGER DEFAULT NULL,
-	"reconciled"	INTEGER NOT NULL DEFAULT 0 CHECK("reconciled" IN (0, 1)),
+	"status"	TEXT NOT NULL DEFAULT 'uncleared' CHECK("status" IN ('uncleared', 'cleared', 'reconciled')),
+	"account"	INTEGER NOT NULL DEFAULT 1 REFERENCES "accounts"("id"),
+	"transfer"	TEXT DEFAULT NULL,
+	"sequence"	INTEGER NOT NULL DEFAULT 0,
+	"fitid"	TEXT DEFAULT NULL,
 	PRIMARY KEY("id")
 ) STRICT;
-INSERT INTO "categories" VALUES (1,'Utilities'),
- (2,'Gifts'),
- (3,'Groceries'),
- (4,'Dining'),
- (5,'Recreation'),
- (6,'Subscriptions'),
- (7,'Opening Balance');
+CREATE INDEX "trades_sequence" ON "trades"("sequence");
+CREATE INDEX "trades_fitid" ON "trades"("account", "fitid");
+DROP TABLE IF EXISTS "splits";
+CREATE TABLE "splits" (
+	"id"	INTEGER,
+	"trade"	TEXT NOT NULL REFERENCES "trades"("id"),
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	"memo"	TEXT DEFAULT '',
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+DROP TABLE IF EXISTS "rules";
+CREATE TABLE "rules" (
+	"id"	INTEGER,
+	"vendor_pattern"	TEXT DEFAULT NULL,
+	"memo_pattern"	TEXT DEFAULT NULL,
+	"regex"	INTEGER NOT NULL DEFAULT 0 CHECK("regex" IN (0, 1)),
+	"minimum"	INTEGER DEFAULT NULL,
+	"maximum"	INTEGER DEFAULT NULL,
+	"transaction_type"	TEXT DEFAULT NULL CHECK("transaction_type" IN ('deposit', 'withdrawal')),
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"vendor"	TEXT DEFAULT NULL,
+	"memo"	TEXT DEFAULT NULL,
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+DROP TABLE IF EXISTS "schedules";
+CREATE TABLE "schedules" (
+	"id"	INTEGER,
+	"vendor"	TEXT NOT NULL,
+	"memo"	TEXT DEFAULT '',
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"account"	INTEGER NOT NULL DEFAULT 1 REFERENCES "accounts"("id"),
+	"recurrence"	TEXT NOT NULL CHECK("recurrence" IN ('weekly', 'biweekly', 'monthly', 'last-business-day')),
+	"day"	INTEGER DEFAULT NULL CHECK("day" BETWEEN 1 AND 31),
+	"start_date"	TEXT NOT NULL DEFAULT (DATE('now')),
+	"last_posted"	TEXT DEFAULT NULL,
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+DROP TABLE IF EXISTS "budgets";
+CREATE TABLE "budgets" (
+	"id"	INTEGER,
+	"category"	INTEGER NOT NULL REFERENCES "categories"("id"),
+	"period"	TEXT NOT NULL,
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY("id" AUTOINCREMENT),
+	UNIQUE("category", "period")
+) STRICT;
+DROP TABLE IF EXISTS "reconciliations";
+CREATE TABLE "reconciliations" (
+	"id"	INTEGER,
+	"account"	INTEGER DEFAULT NULL REFERENCES "accounts"("id"),
+	"statement_date"	TEXT NOT NULL,
+	"statement_balance"	INTEGER NOT NULL DEFAULT 0,
+	"reconciled_on"	TEXT NOT NULL DEFAULT (DATE('now')),
+	"entries"	INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+DROP TABLE IF EXISTS "csv_profiles";
+CREATE TABLE "csv_profiles" (
+	"id"	INTEGER,
+	"name"	TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	"columns"	TEXT NOT NULL,
+	"date_format"	TEXT NOT NULL DEFAULT '%Y-%m-%d',
+	"delimiter"	TEXT NOT NULL DEFAULT ',',
+	"skip_rows"	INTEGER NOT NULL DEFAULT 0,
+	"sign"	TEXT NOT NULL DEFAULT 'deposits-positive' CHECK("sign" IN ('deposits-positive', 'withdrawals-positive')),
+	"decimal_separator"	TEXT NOT NULL DEFAULT '.' CHECK("decimal_separator" IN ('.', ',')),
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+INSERT INTO "accounts" VALUES (1,'Default',0);
+INSERT INTO "categories" VALUES (1,'Utilities',NULL),
+ (2,'Gifts',NULL),
+ (3,'Groceries',NULL),
+ (4,'Dining',NULL),
+ (5,'Recreation',NULL),
+ (6,'Subscriptions',NULL),
+ (7,'Opening Balance',NULL);
 DROP VIEW IF EXISTS "ledger";
 CREATE VIEW ledger AS
 SELECT t.id AS 'id',
 DATE(t.date) AS 'date', 
 t.check_number, 
-CASE t.reconciled
-	WHEN 1
+CASE t.status
+	WHEN 'reconciled'
 		THEN 'Y'
+	WHEN 'cleared'
+		THEN 'C'
 	ELSE 'N'
 END AS 'reconciled',
 t.vendor, 
@@ -39,12 +124,22 @@ t.memo,
 c.category,
 t.amount,
 SUM(t.amount) OVER (
-	ORDER BY DATE(t.date) ASC
+	PARTITION BY t.account
+	ORDER BY DATE(t.date) ASC, t.sequence ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'balance',
+a.name AS 'account',
+SUM(t.amount) OVER (
+	ORDER BY DATE(t.date) ASC, t.sequence ASC
 	ROWS BETWEEN
 	UNBOUNDED PRECEDING
-	AND CURRENT ROW) AS 'balance'
+	AND CURRENT ROW) AS 'total_balance'
 FROM trades t
 LEFT JOIN categories c 
 ON t.category = c.id 
-ORDER BY DATE(t.date) ASC;
+LEFT JOIN accounts a
+ON t.account = a.id 
+ORDER BY DATE(t.date) ASC, t.sequence ASC;
+PRAGMA user_version = 13;
 COMMIT;
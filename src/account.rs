@@ -0,0 +1,101 @@
+use clap::Parser;
+use crate::{ database::*, errors::CheckbookError };
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage the accounts in the register. \r\n\r\nEvery register starts with a single account named Default, which is where entries go when no account is given. \r\n\r\nA new account can be created like this: \r\n\r\nrcheckbook account create --name Savings \r\n\r\nAccounts can then be listed, renamed, or closed. \r\n\r\nClosed accounts keep their entries, but cannot have new entries added to them.")]
+pub struct Account {
+    #[clap(subcommand)]
+    pub subcommand: AccountCommand
+}
+
+#[derive(Parser)]
+pub enum AccountCommand {
+    Create(CreateAccount),
+    List(ListAccounts),
+    Rename(RenameAccount),
+    Close(CloseAccount)
+}
+
+#[derive(Parser)]
+#[clap(about = "Create a new account.")]
+pub struct CreateAccount {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String
+}
+
+#[derive(Parser)]
+#[clap(about = "List accounts along with their balances.")]
+pub struct ListAccounts {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Rename an existing account.")]
+pub struct RenameAccount {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String,
+
+    #[clap(long)]
+    pub new_name: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Close an account, so that no new entries can be added to it.")]
+pub struct CloseAccount {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String
+}
+
+impl Account {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            AccountCommand::Create(c) => c.run(),
+            AccountCommand::List(l) => l.run(),
+            AccountCommand::Rename(r) => r.run(),
+            AccountCommand::Close(c) => c.run()
+        }
+    }
+}
+
+impl CreateAccount {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        add_account_to_db(&db, &self.name)
+    }
+}
+
+impl ListAccounts {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        for account in load_accounts_from_db(&db)? {
+            println!("{}\t{:.2}\t{}", account.name, account.balance, if account.is_closed { "closed" } else { "open" });
+        }
+
+        Ok(())
+    }
+}
+
+impl RenameAccount {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        rename_account_in_db(&db, &self.name, &self.new_name)
+    }
+}
+
+impl CloseAccount {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        close_account_in_db(&db, &self.name)
+    }
+}
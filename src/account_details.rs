@@ -0,0 +1,18 @@
+use fastnum::D64;
+
+/// Represents an account stored in the register, along with its current balance.
+pub struct AccountDetails {
+    pub name: String,
+    pub is_closed: bool,
+    pub balance: D64
+}
+
+impl AccountDetails {
+    pub fn from(name: &str, is_closed: bool, balance: D64) -> Self {
+        Self {
+            name: name.to_string(),
+            is_closed,
+            balance
+        }
+    }
+}
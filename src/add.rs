@@ -1,9 +1,12 @@
 use bcheck::{ Record, Transaction };
 use clap::Parser;
-use crate::database::*;
+use chrono::NaiveDate;
+use crate::{ amount::*, database::*, errors::CheckbookError, rule::apply_rules, split::Split };
+use fastnum::D64;
+use rusqlite::Connection;
 
 #[derive(Parser)]
-#[clap(version = "0.4.1", author = "Bryce Campbell", long_about = "add entry to ledger. \r\n\r\nAdding a new entry can be done like this: \r\n\r\nrcheckbook add --check-number 1260 --vendor \"Sam Hill Credit Union\" -m \"Open Account\" -a 500.0\r\n\r\nIf you want to include a date, you can do so with -d, which expects the date to be in YYYY-MM-DD format.")]
+#[clap(version = "0.4.1", author = "Bryce Campbell", long_about = "add entry to ledger. \r\n\r\nAdding a new entry can be done like this: \r\n\r\nrcheckbook add --check-number 1260 --vendor \"Sam Hill Credit Union\" -m \"Open Account\" -a 500.0\r\n\r\nIf you want to include a date, you can do so with -d, which expects the date to be in YYYY-MM-DD format.\r\n\r\nEntries go into the Default account, unless another account is given with --account.\r\n\r\nAn entry can be split across categories by giving --split once for each category, like this: \r\n\r\nrcheckbook add --vendor \"Fake Street Market\" --split Groceries=-45.20 --split Household=-14.80 \r\n\r\nA memo can be added to a split by appending it, as in Groceries=-45.20=Produce. If no amount is given, the amount will be the total of the splits.\r\n\r\nRules added with the rules command are applied to the new entry, which can fill in its category or tidy up its vendor and memo.")]
 pub struct Add {
 
     #[clap(default_value = "~/.checkbook/register.db")]
@@ -25,31 +28,70 @@ pub struct Add {
     pub memo: String,
 
     #[clap(long, short, allow_hyphen_values = true, default_value = "0.0")]
-    pub amount: f64,
+    pub amount: D64,
 
     #[clap(long, short)]
-    pub reconciled: bool
+    pub reconciled: bool,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long = "split")]
+    pub splits: Vec<Split>
 }
 
 impl Add {
-    pub fn run(&self) {
-        copy_database_if_not_exists(&self.file_path);
-        self.add_record(&self.file_path);
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+
+        if !self.splits.is_empty() && self.category.is_some() {
+            Err(CheckbookError::InvalidInput("Please use either a category or splits, not both.".to_string()))
+        } else if !self.splits.is_empty() && !self.amount.is_zero() && self.amount.round(2) != self.split_total().round(2) {
+            Err(CheckbookError::InvalidInput(format!("The splits add up to {:.2}, but the amount is {:.2}.", self.split_total(), self.amount)))
+        } else if let Some(date) = self.date.as_ref().filter(|date| NaiveDate::parse_from_str(date, qif::DateFormat::FullYearMonthDay.chrono_str()).is_err()) {
+            Err(CheckbookError::Parse(format!("{} is not a valid date. Please use YYYY-MM-DD.", date)))
+        } else {
+            let transaction = db.transaction()?;
+
+            self.add_record(&transaction)?;
+
+            transaction.commit()?;
+
+            Ok(())
+        }
     }
 
-    fn add_record(&self, p: &str) {
-        let transaction = Transaction::builder()
-        .set_date(&self.date.clone().unwrap_or(String::default()))
+    fn split_total(&self) -> D64 {
+        self.splits.iter().fold(D64::ZERO, |sum, split| sum + split.amount)
+    }
+
+    fn add_record(&self, db: &Connection) -> Result<(), CheckbookError> {
+        let amount = if self.splits.is_empty() || !self.amount.is_zero() {
+            self.amount
+        } else {
+            self.split_total()
+        };
+
+        let mut transaction = Transaction::builder()
+        .set_date(&self.date.clone().unwrap_or_default())
         .set_check_number(self.check_number.unwrap_or(0))
-        .set_category(&self.category.clone().unwrap_or(String::default()))
+        .set_category(&self.category.clone().unwrap_or_default())
         .set_vendor(&self.vendor)
         .set_memo(&self.memo)
-        .set_amount_and_type(self.amount)
         .set_is_reconciled(self.reconciled)
         .build();
 
+        set_signed_amount(&mut transaction, amount);
+        apply_rules(&load_rules_from_db(db)?, &mut transaction, !self.splits.is_empty());
+
         let record = Record::from("", transaction);
 
-        add_record_to_db(p, &record);
+        add_record_to_db(db, &record, &self.account)?;
+
+        if !self.splits.is_empty() {
+            replace_splits_in_db(db, &record.id, &self.splits)?;
+        }
+
+        Ok(())
     }
 }
\ No newline at end of file
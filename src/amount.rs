@@ -0,0 +1,48 @@
+use bcheck::{ Transaction, TransactionType };
+use fastnum::D64;
+use crate::errors::CheckbookError;
+
+/// the number of minor units, such as cents, in a single unit of currency.
+const MINOR_UNITS_PER_UNIT: i64 = 100;
+
+/** convert an amount into the whole number of minor units stored in the database.
+ * Amounts too large to be stored are rejected, rather than being stored as something else.
+ */
+pub fn to_minor_units(amount: D64) -> Result<i64, CheckbookError> {
+    i64::try_from((amount * D64::from(MINOR_UNITS_PER_UNIT)).round(0)).map_err(|_| CheckbookError::InvalidInput(format!("{} is too large to be stored.", amount)))
+}
+
+/// convert a whole number of minor units from the database back into an amount.
+pub fn from_minor_units(units: i64) -> D64 {
+    D64::from(units) / D64::from(MINOR_UNITS_PER_UNIT)
+}
+
+/** convert an amount read as a floating point number, as some file formats store them.
+ * The amount is rounded to the nearest minor unit,
+ * so that binary fractions do not end up in the register.
+ */
+pub fn from_float(amount: f64) -> D64 {
+    D64::from_f64(amount).round(2)
+}
+
+/// retrieve the amount of a transaction, which is negative for withdrawals and positive for deposits.
+pub fn signed_amount(t: &Transaction) -> D64 {
+    if let TransactionType::Deposit = t.transaction_type {
+        t.amount
+    } else {
+        -t.amount
+    }
+}
+
+/** set the amount and type of a transaction from an amount like those given to the add command.
+ * Like bcheck, a positive amount is a deposit,
+ * while zero or a negative amount is a withdrawal.
+ */
+pub fn set_signed_amount(t: &mut Transaction, amount: D64) {
+    t.amount = amount.abs();
+    t.transaction_type = if amount > D64::ZERO {
+        TransactionType::Deposit
+    } else {
+        TransactionType::Withdrawal
+    };
+}
@@ -0,0 +1,126 @@
+use chrono::{ Datelike, Local, Months, NaiveDate };
+use clap::Parser;
+use crate::{ database::*, errors::CheckbookError, shared::parse_month };
+use fastnum::D64;
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage how much is planned to be spent in each category every month. \r\n\r\nA budget can be set like this: \r\n\r\nrcheckbook budget set --category Groceries -a 400 \r\n\r\nBudgets are set for the current month, unless another is given with --month, such as 2026-11. Setting a budget for a category that already has one that month replaces it. \r\n\r\nTo start a new month with the same budgets as the last one, do this: \r\n\r\nrcheckbook budget copy-forward \r\n\r\nBudgets that were already set in the new month are kept. \r\n\r\nTo see how spending compares with budgets, use rcheckbook summary --budget.")]
+pub struct Budget {
+    #[clap(subcommand)]
+    pub subcommand: BudgetCommand
+}
+
+#[derive(Parser)]
+pub enum BudgetCommand {
+    Set(SetBudget),
+    List(ListBudgets),
+    CopyForward(CopyBudgets)
+}
+
+#[derive(Parser)]
+#[clap(about = "Set how much is budgeted for a category in a month.")]
+pub struct SetBudget {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub category: String,
+
+    #[clap(long, short)]
+    pub amount: D64,
+
+    #[clap(long)]
+    pub month: Option<String>
+}
+
+#[derive(Parser)]
+#[clap(about = "List the budgets set for a month.")]
+pub struct ListBudgets {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub month: Option<String>
+}
+
+#[derive(Parser)]
+#[clap(about = "Copy the budgets from one month into another.")]
+pub struct CopyBudgets {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub from: Option<String>,
+
+    #[clap(long)]
+    pub to: Option<String>
+}
+
+impl Budget {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            BudgetCommand::Set(s) => s.run(),
+            BudgetCommand::List(l) => l.run(),
+            BudgetCommand::CopyForward(c) => c.run()
+        }
+    }
+}
+
+impl SetBudget {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        if self.amount < D64::ZERO {
+            return Err(CheckbookError::InvalidInput(format!("The budget of {:.2} cannot be negative.", self.amount)));
+        }
+
+        let month = month_or_current(&self.month)?;
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        set_budget_in_db(&db, &self.category, month, self.amount)
+    }
+}
+
+impl ListBudgets {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let month = month_or_current(&self.month)?;
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        for budget in load_budgets_from_db(&db)?.into_iter().filter(|budget| budget.month == month) {
+            println!("{}\t{:.2}", budget.category, budget.amount);
+        }
+
+        Ok(())
+    }
+}
+
+impl CopyBudgets {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let to = month_or_current(&self.to)?;
+
+        let from = match &self.from {
+            Some(from) => parse_month(from)?,
+            None => to - Months::new(1)
+        };
+
+        if from == to {
+            return Err(CheckbookError::InvalidInput("Budgets cannot be copied into the month they come from.".to_string()));
+        }
+
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        let copied = copy_budgets_in_db(&db, from, to)?;
+
+        println!("Copied {} budgets.", copied);
+
+        Ok(())
+    }
+}
+
+/// retrieve the first day of the given month, or of the current month if none is given.
+fn month_or_current(month: &Option<String>) -> Result<NaiveDate, CheckbookError> {
+    match month {
+        Some(month) => parse_month(month),
+        None => {
+            let today = Local::now().date_naive();
+            Ok(today.with_day(1).unwrap_or(today))
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use clap::Parser;
+use crate::{ category_path::normalized_path, database::*, errors::CheckbookError };
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage the categories in the register. \r\n\r\nCategories are added automatically when an entry uses one that does not exist yet, but they can also be added ahead of time like this: \r\n\r\nrcheckbook category add --name Household \r\n\r\nCategories can be nested by separating names with colons, like Utilities:Electric, and any missing parents are added along with them. \r\n\r\nCategories can be listed along with how often they are used, renamed, merged into another category, or deleted. \r\n\r\nA category that is still used cannot be deleted, unless another category is given with --reassign to move its entries to. Categories with others nested under them cannot be merged or deleted.")]
+pub struct Category {
+    #[clap(subcommand)]
+    pub subcommand: CategoryCommand
+}
+
+#[derive(Parser)]
+pub enum CategoryCommand {
+    List(ListCategories),
+    Add(AddCategory),
+    Rename(RenameCategory),
+    Merge(MergeCategory),
+    Delete(DeleteCategory)
+}
+
+#[derive(Parser)]
+#[clap(about = "List categories along with how many entries use them and their total.")]
+pub struct ListCategories {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Add a new category.")]
+pub struct AddCategory {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Rename an existing category.")]
+pub struct RenameCategory {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String,
+
+    #[clap(long)]
+    pub new_name: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Move every entry in a category into another, then remove the first category.")]
+pub struct MergeCategory {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String,
+
+    #[clap(long)]
+    pub into: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Delete a category, moving its entries to another category if one is given.")]
+pub struct DeleteCategory {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub name: String,
+
+    #[clap(long)]
+    pub reassign: Option<String>
+}
+
+impl Category {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            CategoryCommand::List(l) => l.run(),
+            CategoryCommand::Add(a) => a.run(),
+            CategoryCommand::Rename(r) => r.run(),
+            CategoryCommand::Merge(m) => m.run(),
+            CategoryCommand::Delete(d) => d.run()
+        }
+    }
+}
+
+impl ListCategories {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        for category in load_category_details_from_db(&db)? {
+            println!("{}\t{}\t{:.2}", category.name, category.usage, category.total);
+        }
+
+        Ok(())
+    }
+}
+
+impl AddCategory {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        if is_uncategorized(&self.name) {
+            Err(CheckbookError::InvalidInput("Uncategorized is used for entries without a category.".to_string()))
+        } else if category_exists_in_db(&db, &self.name)? {
+            Err(CheckbookError::CategoryExists(self.name.clone()))
+        } else {
+            add_category_to_db(&db, &self.name)
+        }
+    }
+}
+
+impl RenameCategory {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        if is_uncategorized(&self.new_name) {
+            Err(CheckbookError::InvalidInput("Uncategorized is used for entries without a category.".to_string()))
+        } else {
+            rename_category_in_db(&db, &self.name, &self.new_name)
+        }
+    }
+}
+
+impl MergeCategory {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+        let transaction = db.transaction()?;
+
+        merge_category_in_db(&transaction, &self.name, &self.into)?;
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+impl DeleteCategory {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+        let transaction = db.transaction()?;
+
+        delete_category_from_db(&transaction, &self.name, &self.reassign)?;
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+/// check if the given name is the one used for entries without a category.
+fn is_uncategorized(name: &str) -> bool {
+    let name = normalized_path(name);
+
+    name.is_empty() || name.to_lowercase() == "uncategorized"
+}
\ No newline at end of file
@@ -0,0 +1,18 @@
+use fastnum::D64;
+
+/// Represents a category stored in the register, along with how much it is used.
+pub struct CategoryDetails {
+    pub name: String,
+    pub usage: u32,
+    pub total: D64
+}
+
+impl CategoryDetails {
+    pub fn from(name: &str, usage: u32, total: D64) -> Self {
+        Self {
+            name: name.to_string(),
+            usage,
+            total
+        }
+    }
+}
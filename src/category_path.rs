@@ -0,0 +1,43 @@
+/// the character that separates a category from its parent, like in QIF files.
+pub const SEPARATOR: char = ':';
+
+/** tidy up a category path, such as Utilities : Electric, into Utilities:Electric.
+ * Space around each name and empty names are dropped.
+ */
+pub fn normalized_path(c: &str) -> String {
+    c.split(SEPARATOR)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<&str>>()
+        .join(&SEPARATOR.to_string())
+}
+
+/// retrieve the path of the category that the given category is nested under, if any.
+pub fn parent_path(c: &str) -> Option<&str> {
+    c.rsplit_once(SEPARATOR).map(|(parent, _)| parent)
+}
+
+/// retrieve the name of the category without any of its parents.
+pub fn leaf_name(c: &str) -> &str {
+    c.rsplit_once(SEPARATOR).map(|(_, name)| name).unwrap_or(c)
+}
+
+/// retrieve how many parents a category has.
+pub fn depth(c: &str) -> usize {
+    c.matches(SEPARATOR).count()
+}
+
+/// check if a category is the given ancestor or nested anywhere under it, ignoring case.
+pub fn is_within(c: &str, ancestor: &str) -> bool {
+    let category = c.to_lowercase();
+    let ancestor = ancestor.to_lowercase();
+
+    category == ancestor || category.starts_with(&format!("{}{}", ancestor, SEPARATOR))
+}
+
+/** retrieve a key that sorts categories alphabetically,
+ * with each category directly followed by the categories nested under it.
+ */
+pub fn sort_key(c: &str) -> Vec<String> {
+    c.to_lowercase().split(SEPARATOR).map(str::to_string).collect()
+}
@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+use crate::shared::display_value_name;
+
+/** Represents what an import does with entries that are already in the register but differ from the file.
+ * Overwriting is what imports have always done,
+ * while skipping keeps the register as it is, and failing stops the import altogether.
+ */
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    Fail
+}
+
+display_value_name!(ConflictPolicy);
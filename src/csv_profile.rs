@@ -0,0 +1,369 @@
+use bcheck::{ Record, Transaction };
+use chrono::NaiveDate;
+use csv::{ ByteRecord, ReaderBuilder, Trim };
+use fastnum::D64;
+use std::{ fmt, fs };
+use crate::{ amount::set_signed_amount, category_path::normalized_path, errors::CheckbookError, shared::decode_text, sign_convention::SignConvention };
+
+/// the date format used when none is given, which is the same one the register uses.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// the fields that columns can be mapped to, in the order they are written out.
+const FIELDS: [&str; 8] = ["date", "payee", "memo", "amount", "debit", "credit", "check", "category"];
+
+/** Represents which columns of a CSV file hold each part of an entry.
+ * Columns are counted from 1, like they are in a spreadsheet.
+ */
+#[derive(Clone, Debug)]
+pub struct CsvColumns {
+    pub date: usize,
+    pub payee: usize,
+    pub memo: Option<usize>,
+    pub amount: Option<usize>,
+    pub debit: Option<usize>,
+    pub credit: Option<usize>,
+    pub check_number: Option<usize>,
+    pub category: Option<usize>
+}
+
+impl CsvColumns {
+    /** parse a mapping such as date=1,payee=3,amount=5.
+     * A date and payee must be mapped,
+     * along with either a single amount column or separate debit and credit columns.
+     */
+    pub fn parse(mapping: &str) -> Result<Self, CheckbookError> {
+        let mut date = None;
+        let mut payee = None;
+        let mut memo = None;
+        let mut amount = None;
+        let mut debit = None;
+        let mut credit = None;
+        let mut check_number = None;
+        let mut category = None;
+
+        for pair in mapping.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let Some((field, column)) = pair.split_once('=') else {
+                return Err(CheckbookError::InvalidInput(format!("{} is not a valid column mapping. Please use field=column, such as date=1.", pair.trim())));
+            };
+
+            let column = column.trim().parse::<usize>().ok().filter(|column| *column > 0).ok_or(CheckbookError::InvalidInput(format!("column {} for {} must be a number 1 or greater.", column.trim(), field.trim())))?;
+
+            match field.trim().to_lowercase().as_str() {
+                "date" => date = Some(column),
+                "payee" | "vendor" => payee = Some(column),
+                "memo" => memo = Some(column),
+                "amount" => amount = Some(column),
+                "debit" => debit = Some(column),
+                "credit" => credit = Some(column),
+                "check" | "check_number" => check_number = Some(column),
+                "category" => category = Some(column),
+                field => return Err(CheckbookError::InvalidInput(format!("{} cannot be mapped to a column. Please use one of {}.", field, FIELDS.join(", "))))
+            }
+        }
+
+        let (Some(date), Some(payee)) = (date, payee) else {
+            return Err(CheckbookError::InvalidInput("Please map both the date and payee columns.".to_string()));
+        };
+
+        if amount.is_some() == (debit.is_some() || credit.is_some()) {
+            return Err(CheckbookError::InvalidInput("Please map either an amount column, or debit and credit columns.".to_string()));
+        }
+
+        Ok(Self { date, payee, memo, amount, debit, credit, check_number, category })
+    }
+}
+
+impl fmt::Display for CsvColumns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns = [Some(self.date), Some(self.payee), self.memo, self.amount, self.debit, self.credit, self.check_number, self.category];
+
+        let mapping: Vec<String> = FIELDS.iter().zip(columns).filter_map(|(field, column)| column.map(|column| format!("{}={}", field, column))).collect();
+
+        write!(f, "{}", mapping.join(","))
+    }
+}
+
+/** Represents how to read the CSV files of a particular bank.
+ * Profiles can be saved under a name,
+ * so that the same layout does not have to be given on every import.
+ */
+#[derive(Clone, Debug)]
+pub struct CsvProfile {
+    pub name: String,
+    pub columns: CsvColumns,
+    pub date_format: String,
+    pub delimiter: char,
+    pub skip_rows: u32,
+    pub sign: SignConvention,
+    pub decimal_separator: char
+}
+
+impl CsvProfile {
+    /** retrieve the records in the given file, after skipping the given number of lines, along with the rows that could not be read.
+     * Blank rows are passed over.
+     * Lines are counted up to the first byte of each row,
+     * since the csv crate reports rows as starting at the line break before them,
+     * and miscounts lines that end in CRLF.
+     */
+    pub fn records_from_file(&self, p: &str) -> Result<(Vec<Record>, Vec<CheckbookError>), CheckbookError> {
+        let delimiter = u8::try_from(self.delimiter).map_err(|_| CheckbookError::InvalidInput(format!("{} cannot be used as a delimiter. Please use a single ASCII character.", self.delimiter)))?;
+
+        let content = fs::read(p).map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", p, error)))?;
+
+        let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(content.as_slice());
+
+        let mut records = vec![];
+        let mut invalid_rows = vec![];
+
+        // line breaks are counted as rows are read, since rows can span lines when quoted fields hold line breaks.
+        let mut counted_bytes = 0;
+        let mut line = 1;
+
+        for row in reader.byte_records() {
+            let row = row.map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", p, error)))?;
+            let position = row.position().and_then(|position| usize::try_from(position.byte()).ok()).unwrap_or_default().max(counted_bytes);
+            let start = content[position..].iter().position(|byte| !matches!(byte, b'\r' | b'\n')).map(|offset| position + offset).unwrap_or(content.len());
+
+            line += content[counted_bytes..start].iter().filter(|byte| **byte == b'\n').count() as u64;
+            counted_bytes = start;
+
+            if line <= u64::from(self.skip_rows) || row.iter().all(|field| field.is_empty()) {
+                continue;
+            }
+
+            match self.record_from_row(&row) {
+                Ok(record) => records.push(record),
+                Err(error) => invalid_rows.push(CheckbookError::Parse(format!("line {}: {}", line, error)))
+            }
+        }
+
+        Ok((records, invalid_rows))
+    }
+
+    fn record_from_row(&self, row: &ByteRecord) -> Result<Record, CheckbookError> {
+        let field = |column: Option<usize>| -> String {
+            column.and_then(|column| row.get(column - 1)).map(|value| decode_text(value).trim().to_string()).unwrap_or_default()
+        };
+
+        let date_text = field(Some(self.columns.date));
+        let date = NaiveDate::parse_from_str(&date_text, &self.date_format).map_err(|_| CheckbookError::Parse(format!("{} does not match the date format {}.", date_text, self.date_format)))?;
+
+        let check_text = field(self.columns.check_number);
+        let check_number = if check_text.is_empty() {
+            0
+        } else {
+            check_text.parse::<u32>().map_err(|_| CheckbookError::Parse(format!("check number {} must be a number 0 or greater.", check_text)))?
+        };
+
+        let amount = if self.columns.amount.is_some() {
+            let amount = parse_amount(&field(self.columns.amount), self.decimal_separator)?.ok_or(CheckbookError::Parse("the amount is missing.".to_string()))?;
+
+            if let SignConvention::WithdrawalsPositive = self.sign { -amount } else { amount }
+        } else {
+            let (debit, credit) = match (parse_amount(&field(self.columns.debit), self.decimal_separator)?, parse_amount(&field(self.columns.credit), self.decimal_separator)?) {
+                (None, None) => return Err(CheckbookError::Parse("the debit and credit are both missing.".to_string())),
+                (debit, credit) => (debit.unwrap_or(D64::ZERO).abs(), credit.unwrap_or(D64::ZERO).abs())
+            };
+
+            if debit > D64::ZERO && credit > D64::ZERO {
+                return Err(CheckbookError::TransactionTypeParsingError);
+            }
+
+            if credit > D64::ZERO { credit } else { -debit }
+        };
+
+        let mut transaction = Transaction::builder()
+        .set_date(&date.format(DEFAULT_DATE_FORMAT).to_string())
+        .set_check_number(check_number)
+        .set_category(&normalized_path(&field(self.columns.category)))
+        .set_vendor(&field(Some(self.columns.payee)))
+        .set_memo(&field(self.columns.memo))
+        .build();
+
+        set_signed_amount(&mut transaction, amount);
+
+        Ok(Record::from("", transaction))
+    }
+}
+
+impl fmt::Display for CsvProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let delimiter = if self.delimiter == '\t' { "tab".to_string() } else { self.delimiter.to_string() };
+
+        write!(f, "{}\t{}\t{}\t{}\t{}\t{}\t{}", self.name, self.columns, self.date_format, delimiter, self.skip_rows, self.sign, self.decimal_separator)
+    }
+}
+
+/** parse an amount the way banks tend to write them, such as $1,234.56, -12.00, (12.00), or 1.234,56 with a decimal comma.
+ * Amounts in parentheses or followed by a minus sign are negative,
+ * and an empty field has no amount at all.
+ * Whichever of commas and periods is not the decimal separator is taken to separate thousands.
+ */
+fn parse_amount(text: &str, decimal_separator: char) -> Result<Option<D64>, CheckbookError> {
+    let thousands_separator = if decimal_separator == ',' { '.' } else { ',' };
+
+    let cleaned: String = text.chars()
+    .filter(|character| !matches!(character, '$' | '£' | '€' | '¥' | ' ' | '\u{a0}') && *character != thousands_separator)
+    .map(|character| if character == decimal_separator { '.' } else { character })
+    .collect();
+
+    if cleaned.is_empty() {
+        return Ok(None);
+    }
+
+    let (is_negative, digits) = if let Some(digits) = cleaned.strip_prefix('(').and_then(|digits| digits.strip_suffix(')')) {
+        (true, digits)
+    } else if let Some(digits) = cleaned.strip_suffix('-') {
+        (true, digits)
+    } else {
+        (false, cleaned.as_str())
+    };
+
+    let amount = digits.parse::<D64>().map_err(|_| CheckbookError::Parse(format!("{} is not a valid amount.", text)))?;
+
+    Ok(Some(if is_negative { -amount } else { amount }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::signed_amount;
+
+    #[test]
+    fn reads_signed_amounts() {
+        let profile = CsvProfile {
+            name: String::default(),
+            columns: CsvColumns::parse("date=2,payee=3,amount=4,check=7").expect("could not parse columns"),
+            date_format: "%m/%d/%Y".to_string(),
+            delimiter: ',',
+            skip_rows: 1,
+            sign: SignConvention::DepositsPositive,
+            decimal_separator: '.'
+        };
+
+        let (records, invalid_rows) = profile.records_from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/csv/chase_checking.csv")).expect("could not read fixture");
+
+        let dates: Vec<String> = records.iter().map(|record| record.transaction.date.format("%Y-%m-%d").to_string()).collect();
+        let check_numbers: Vec<Option<u32>> = records.iter().map(|record| record.transaction.check_number.filter(|check_number| *check_number > 0)).collect();
+        let vendors: Vec<&str> = records.iter().map(|record| record.transaction.vendor.as_str()).collect();
+        let amounts: Vec<String> = records.iter().map(|record| format!("{:.2}", signed_amount(&record.transaction))).collect();
+        let errors: Vec<String> = invalid_rows.iter().map(|error| error.to_string()).collect();
+
+        assert_eq!(dates, vec!["2026-10-01", "2026-10-02", "2026-10-03"]);
+        assert_eq!(check_numbers, vec![None, None, Some(1042)]);
+        assert_eq!(vendors, vec!["STARBUCKS STORE 1234, SEATTLE", "PAYROLL ACME INC", "CHECK 1042"]);
+        assert_eq!(amounts, vec!["-5.75", "1500.00", "-120.00"]);
+        assert_eq!(errors, vec!["line 5: the amount is missing."]);
+    }
+
+    #[test]
+    fn reads_withdrawals_written_as_positive_amounts() {
+        let profile = CsvProfile {
+            name: String::default(),
+            columns: CsvColumns::parse("date=1,payee=2,amount=3").expect("could not parse columns"),
+            date_format: "%m/%d/%y".to_string(),
+            delimiter: ',',
+            skip_rows: 1,
+            sign: SignConvention::WithdrawalsPositive,
+            decimal_separator: '.'
+        };
+
+        let (records, invalid_rows) = profile.records_from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/csv/amex.csv")).expect("could not read fixture");
+
+        let dates: Vec<String> = records.iter().map(|record| record.transaction.date.format("%Y-%m-%d").to_string()).collect();
+        let amounts: Vec<String> = records.iter().map(|record| format!("{:.2}", signed_amount(&record.transaction))).collect();
+
+        assert_eq!(dates, vec!["2026-10-07", "2026-10-08"]);
+        assert_eq!(amounts, vec!["-34.99", "10.00"]);
+        assert!(invalid_rows.is_empty());
+    }
+
+    #[test]
+    fn reads_separate_debit_and_credit_columns() {
+        let profile = CsvProfile {
+            name: String::default(),
+            columns: CsvColumns::parse("date=1,payee=4,category=5,debit=6,credit=7").expect("could not parse columns"),
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            delimiter: ',',
+            skip_rows: 1,
+            sign: SignConvention::DepositsPositive,
+            decimal_separator: '.'
+        };
+
+        let (records, invalid_rows) = profile.records_from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/csv/capital_one_card.csv")).expect("could not read fixture");
+
+        let categories: Vec<Option<String>> = records.iter().map(|record| record.transaction.category.clone()).collect();
+        let vendors: Vec<&str> = records.iter().map(|record| record.transaction.vendor.as_str()).collect();
+        let amounts: Vec<String> = records.iter().map(|record| format!("{:.2}", signed_amount(&record.transaction))).collect();
+        let errors: Vec<String> = invalid_rows.iter().map(|error| error.to_string()).collect();
+
+        // the fixture is written in Latin-1, as some banks still do.
+        assert_eq!(vendors, vec!["Café Rouge", "CAPITAL ONE AUTOPAY", "SHELL OIL 5744"]);
+        assert_eq!(categories, vec![Some("Dining".to_string()), Some("Payment/Credit".to_string()), Some("Gas/Automotive".to_string())]);
+        assert_eq!(amounts, vec!["-23.40", "200.00", "-41.17"]);
+        assert_eq!(errors, vec!["line 5: the debit and credit are both missing."]);
+    }
+
+    #[test]
+    fn skips_summary_above_transactions() {
+        let profile = CsvProfile {
+            name: String::default(),
+            columns: CsvColumns::parse("date=1,payee=2,amount=3").expect("could not parse columns"),
+            date_format: "%m/%d/%Y".to_string(),
+            delimiter: ',',
+            skip_rows: 8,
+            sign: SignConvention::DepositsPositive,
+            decimal_separator: '.'
+        };
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/csv/bank_of_america_crlf.csv");
+        let (records, invalid_rows) = profile.records_from_file(path).expect("could not read fixture");
+
+        let dates: Vec<String> = records.iter().map(|record| record.transaction.date.format("%Y-%m-%d").to_string()).collect();
+        let vendors: Vec<&str> = records.iter().map(|record| record.transaction.vendor.as_str()).collect();
+        let amounts: Vec<String> = records.iter().map(|record| format!("{:.2}", signed_amount(&record.transaction))).collect();
+
+        assert_eq!(dates, vec!["2026-10-05", "2026-10-15", "2026-10-20"]);
+        assert_eq!(vendors, vec!["GROCERY OUTLET", "PAYROLL ACME INC", "CHECK 1043"]);
+        assert_eq!(amounts, vec!["-45.10", "1500.00", "-80.65"]);
+        assert!(invalid_rows.is_empty());
+
+        // the file has CRLF line endings, which should not throw off the line numbers of rows that cannot be read.
+        let (records, invalid_rows) = CsvProfile { skip_rows: 1, ..profile }.records_from_file(path).expect("could not read fixture");
+        let errors: Vec<String> = invalid_rows.iter().map(|error| error.to_string().split(':').next().unwrap_or_default().to_string()).collect();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(errors, vec!["line 2", "line 3", "line 4", "line 5", "line 7", "line 8"]);
+    }
+
+    #[test]
+    fn reads_decimal_commas() {
+        let profile = CsvProfile {
+            name: String::default(),
+            columns: CsvColumns::parse("date=2,payee=6,memo=5,amount=9").expect("could not parse columns"),
+            date_format: "%d.%m.%Y".to_string(),
+            delimiter: ';',
+            skip_rows: 1,
+            sign: SignConvention::DepositsPositive,
+            decimal_separator: ','
+        };
+
+        let (records, invalid_rows) = profile.records_from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/csv/sparkasse.csv")).expect("could not read fixture");
+
+        let dates: Vec<String> = records.iter().map(|record| record.transaction.date.format("%Y-%m-%d").to_string()).collect();
+        let vendors: Vec<&str> = records.iter().map(|record| record.transaction.vendor.as_str()).collect();
+        let memos: Vec<&str> = records.iter().map(|record| record.transaction.memo.as_str()).collect();
+        let amounts: Vec<String> = records.iter().map(|record| format!("{:.2}", signed_amount(&record.transaction))).collect();
+
+        assert_eq!(dates, vec!["2026-10-01", "2026-10-02", "2026-10-30"]);
+        assert_eq!(vendors, vec!["Hausverwaltung Müller", "REWE Markt", "ACME GmbH"]);
+        assert_eq!(memos, vec!["Miete Oktober", "Einkauf", "Gehalt Oktober"]);
+        assert_eq!(amounts, vec!["-1250.00", "-45.67", "3210.50"]);
+        assert!(invalid_rows.is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+use clap::Parser;
+use crate::{ database::*, errors::CheckbookError };
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage the saved layouts of CSV files. \r\n\r\nProfiles are saved when importing, like this: \r\n\r\nrcheckbook import -i statement.csv --columns date=1,payee=3,amount=5 --date-format %m/%d/%Y --skip-rows 1 --save-profile checking \r\n\r\nand can then be used to import later files from the same bank: \r\n\r\nrcheckbook import -i statement.csv --profile checking \r\n\r\nSaving a profile under a name that is already taken replaces it.")]
+pub struct CsvProfiles {
+    #[clap(subcommand)]
+    pub subcommand: CsvProfilesCommand
+}
+
+#[derive(Parser)]
+pub enum CsvProfilesCommand {
+    List(ListCsvProfiles),
+    Delete(DeleteCsvProfile)
+}
+
+#[derive(Parser)]
+#[clap(about = "List saved profiles along with their columns, date format, delimiter, rows skipped, sign convention, and decimal separator.")]
+pub struct ListCsvProfiles {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Delete a saved profile.")]
+pub struct DeleteCsvProfile {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub name: String
+}
+
+impl CsvProfiles {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            CsvProfilesCommand::List(l) => l.run(),
+            CsvProfilesCommand::Delete(d) => d.run()
+        }
+    }
+}
+
+impl ListCsvProfiles {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        for profile in load_csv_profiles_from_db(&db)? {
+            println!("{}", profile);
+        }
+
+        Ok(())
+    }
+}
+
+impl DeleteCsvProfile {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        delete_csv_profile_from_db(&db, &self.name)
+    }
+}
@@ -1,283 +1,1247 @@
-use std::{ fs::{ self, File }, path::{ Path, PathBuf }, io::{ self, Read } };
+use std::{ fs, path::Path };
 
 use std::env;
+use std::collections::HashMap;
 
-use rusqlite::{ Connection, params };
+use rusqlite::{ Connection, OpenFlags, OptionalExtension, params, Row };
 use bcheck::{ Record, Transaction, TransactionType };
-use crate::shared::*;
+use chrono::NaiveDate;
+use fastnum::D64;
+use clap::ValueEnum;
+use crate::{ account_details::AccountDetails, amount::*, category_details::CategoryDetails, category_path::*, csv_profile::{ CsvColumns, CsvProfile }, errors::CheckbookError, ledger_entry::LedgerEntry, migrations::*, monthly_budget::MonthlyBudget, reconciliation::Reconciliation, recurrence::Recurrence, rule::{ Pattern, Rule }, scheduled_entry::ScheduledEntry, shared::*, sign_convention::SignConvention, split::Split, status::Status };
 
-pub fn copy_database_if_not_exists(p: &str) {
+const DEFAULT_ACCOUNT_ID: i32 = 1;
+
+/// the schema that new databases are created from, unless another one is given with REGISTRY_SCHEMA_DIR.
+const REGISTER_SCHEMA: &str = include_str!("../register.sql");
+
+/** create the database from the schema if it does not exist yet, then open it.
+ * If the schema does not create a usable database,
+ * the new database is removed, instead of being left empty.
+ */
+pub fn copy_database_if_not_exists(p: &str) -> Result<Connection, CheckbookError> {
     let target = real_path(p);
     let destination_path = Path::new(&target);
 
-    #[cfg(windows)]
-    let schema_file: PathBuf = if let Ok(schema_dir) = env::var("REGISTRY_SCHEMA_DIR") {
-        Path::new(&real_path(&schema_dir)).join("register.sql").to_path_buf()
-    } else if let Ok(path) = env::current_exe() {
-        if let Some(db_directory) = path.parent() {
-            db_directory.join("register.sql")
-        } else {
-            Path::new("register.sql").to_path_buf()  
+    if !destination_path.exists() {
+        let schema = schema_sql()?;
+
+        if let Some(parent) = destination_path.parent() {
+            fs::create_dir_all(parent)?;
         }
-    } else {
-        Path::new("register.sql").to_path_buf()
-    };
 
-    #[cfg(unix)]
-    let schema_file: PathBuf = if let Ok(schema_dir) = env::var("REGISTRY_SCHEMA_DIR") {
-        Path::new(&real_path(&schema_dir)).join("register.sql").to_path_buf()
-    } else {
-        Path::new(&real_path("/usr/local/share/rcheckbook/register.sql")).to_path_buf()
-    };
-    
+        let result = Connection::open(destination_path).map_err(CheckbookError::from).and_then(|db| {
+            db.execute_batch(&schema).map_err(|error| CheckbookError::Schema(error.to_string()))?;
 
-    if !destination_path.exists() {
-        let _ = fs::create_dir_all(destination_path.parent().unwrap());
-
-        if let Ok(db) = Connection::open(&destination_path) {
-            if let Some(schema_path_string) = schema_file.as_path().as_os_str().to_str() {
-                match file_content(schema_path_string) {
-                    Ok(sql) => if let Err(error) = db.execute_batch(&sql) {
-                        println!("{}", error)
-                    },
-                    Err(error) => println!("{}", error)
-                }
+            if table_exists(&db, "trades")? {
+                Ok(())
+            } else {
+                Err(CheckbookError::Schema("schema does not create the trades table.".to_string()))
             }
+        });
+
+        if let Err(error) = result {
+            let _ = fs::remove_file(destination_path);
+            return Err(error);
         }
     }
 
+    open_database(p)
 }
 
-pub fn load_records_from_db(p: &str) -> Vec<Record> {
-    let mut stored_records: Vec<Record> = vec![];
-
-    match Connection::open(&real_path(p)) {
-        Ok(db) => {
-            if let Ok(mut statement) = db.prepare("SELECT * from ledger") {
-                let record_query = statement.query_map([], |row| {
-                    let id: String = row.get_unwrap(0);
-
-                    let date: String = row.get_unwrap(1);
-                    let check_number: u32 = if let Ok(num) = row.get(2) {
-                        num
-                    } else {
-                        0
-                    };
-
-                    let category: String = if let Ok(c) = row.get(6) {
-                        c
-                    } else {
-                        String::default()
-                    };
-
-                    let vendor: String = row.get_unwrap(4);
-
-                    let memo: String = row.get_unwrap(5);
-
-                    let amount: f64 = if let Ok(a) = row.get(7) {
-                        a
-                    } else {
-                        0.0
-                    };
-
-                    let is_reconciled: String = if let Ok(r) = row.get(3) {
-                        r
-                    } else {
-                        "N".to_string()
-                    };
-
-                    let transaction = Transaction::builder()
-                    .set_date(&date)
-                    .set_check_number(check_number)
-                    .set_category(&category)
-                    .set_vendor(&vendor)
-                    .set_memo(&memo)
-                    .set_amount_and_type(amount)
-                    .set_is_reconciled(is_reconciled.to_lowercase() == "y")
-                    .build();
-
-                    Ok(Record::from(&id, transaction))
-                }).unwrap();
-                
-                for row in record_query {
-                    if let Ok(record) = row {
-                        stored_records.push(record);
-                    }
-                }
-            }
-        },
-        _ => {}
+/** retrieve the schema used to create new databases.
+ * The schema built into the program is used,
+ * unless REGISTRY_SCHEMA_DIR is set to the directory of another register.sql.
+ */
+fn schema_sql() -> Result<String, CheckbookError> {
+    if let Ok(schema_dir) = env::var("REGISTRY_SCHEMA_DIR") {
+        let schema_file = Path::new(&real_path(&schema_dir)).join("register.sql");
+
+        fs::read_to_string(&schema_file).map_err(|error| CheckbookError::Schema(format!("could not read schema file {}: {}", schema_file.display(), error)))
+    } else {
+        Ok(REGISTER_SCHEMA.to_string())
     }
+}
 
-    stored_records
+/** open an existing database and bring it up to date.
+ * The connection is meant to be used for everything a command does,
+ * so that statements can be prepared once and reused.
+ */
+pub fn open_database(p: &str) -> Result<Connection, CheckbookError> {
+    let mut db = connect_to_database(p)?;
+
+    migrate_database(&mut db, p)?;
+
+    Ok(db)
 }
 
-pub fn load_categories_from_db(p: &str) -> Vec<String> {
-    let mut stored_categories: Vec<String> = vec![];
+/// open an existing database as it is, without creating one if it is missing.
+pub fn connect_to_database(p: &str) -> Result<Connection, CheckbookError> {
+    let target = real_path(p);
 
-    if let Ok(db) = Connection::open(&real_path(p)) {
-        if let Ok(mut statement) = db.prepare("SELECT category FROM categories") {
-            let category_query = statement.query_map([], |row| {
-                let name: String = row.get_unwrap(0);
-                Ok(name)
-            }).unwrap();
+    if !Path::new(&target).exists() {
+        return Err(CheckbookError::DatabaseUnreadable(format!("{} does not exist.", p)));
+    }
 
-            for category in category_query {
-                stored_categories.push(category.unwrap());
-            }
-        }
-    } else {}
-    stored_categories
+    Connection::open(&target).map_err(|error| CheckbookError::DatabaseUnreadable(format!("{}: {}", p, error)))
 }
 
-pub fn retrieve_balance_for_record(p: &str, r: Record) -> f64 {
-    let mut balance = 0.0;
-    if let Ok(db) = Connection::open(&real_path(p)){
-        let balance_query_string = format!("SELECT balance FROM ledger WHERE id = '{}'", r.id);
-        balance = db.query_row(&balance_query_string, [], |row| row.get(0)).unwrap();
-    } else {}
+/** open an existing database without changing it, for commands that only look at the register.
+ * A missing database is not created, and one that needs migrating is not migrated,
+ * so both are errors instead.
+ */
+pub fn open_database_read_only(p: &str) -> Result<Connection, CheckbookError> {
+    let target = real_path(p);
+
+    if !Path::new(&target).exists() {
+        return Err(CheckbookError::DatabaseUnreadable(format!("{} does not exist.", p)));
+    }
 
-    balance
+    let db = Connection::open_with_flags(&target, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX).map_err(|error| CheckbookError::DatabaseUnreadable(format!("{}: {}", p, error)))?;
+    let version = database_version(&db)?;
+
+    if version > latest_version() {
+        return Err(CheckbookError::UnsupportedVersion(version));
+    }
+
+    if version < latest_version() {
+        return Err(CheckbookError::DatabaseUnreadable(format!("{} is at version {} and needs to be migrated to version {} first. Please run rcheckbook db migrate.", p, version, latest_version())));
+    }
+
+    Ok(db)
 }
 
-fn category_exists_in_db(p: &str, c: &str) -> bool {
-    let categories: Vec<String> = load_categories_from_db(p).iter().map(|e| e.to_lowercase()).collect();
-    categories.contains(&c.to_lowercase())
+/** retrieve the schema version of the database.
+ * Databases made before versions were recorded are version 0,
+ * which is the schema the program originally shipped with.
+ */
+pub fn database_version(db: &Connection) -> Result<i32, CheckbookError> {
+    db.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(|error| CheckbookError::DatabaseUnreadable(error.to_string()))
 }
 
-fn category_id(p: &str, c: &str) -> Option<i32> {
-    let mut category_id: Option<i32> = None;
-    if category_exists_in_db(p, c) {
-        if let Ok(db) = Connection::open(&real_path(p)) {
-            let category_sql = format!("SELECT id FROM categories WHERE category = '{}' COLLATE NOCASE", c);
-            category_id = if let Ok(value) = db.query_row(&category_sql, [], |row| row.get(0)) {
-                Some(value)
-            } else {
-                None
+/** bring the database up to the latest version of the schema.
+ * A copy of the database is made before any migration is run,
+ * and each migration is applied in its own transaction.
+ */
+pub fn migrate_database(db: &mut Connection, p: &str) -> Result<(), CheckbookError> {
+    let version = database_version(db)?;
+
+    if version > latest_version() {
+        return Err(CheckbookError::UnsupportedVersion(version));
+    }
+
+    if version == latest_version() {
+        return Ok(());
+    }
+
+    let backup_path = format!("{}.v{}.bak", real_path(p), version);
+
+    fs::copy(real_path(p), &backup_path)?;
+
+    println!("Backed up database to {}", backup_path);
+
+    // tables are altered and rebuilt by migrations, which foreign keys would otherwise prevent.
+    db.execute_batch("PRAGMA foreign_keys = OFF;")?;
+
+    let result = run_migrations(db, version);
+
+    // foreign keys are turned back on whether or not the migrations succeeded, since the connection is still used afterwards.
+    db.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+    result?;
+
+    let violations: i64 = db.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))?;
+
+    if violations > 0 {
+        return Err(CheckbookError::Migration(latest_version(), format!("{} rows refer to rows that do not exist. The database from before the migration is at {}.", violations, backup_path)));
+    }
+
+    Ok(())
+}
+
+/// apply each migration newer than the given version, stopping at the first one that fails.
+fn run_migrations(db: &mut Connection, version: i32) -> Result<(), CheckbookError> {
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > version) {
+        let result = db.transaction().and_then(|transaction| {
+            for statement in migration.statements {
+                transaction.execute_batch(statement)?;
             }
+
+            transaction.pragma_update(None, "user_version", migration.version)?;
+            transaction.commit()
+        });
+
+        if let Err(error) = result {
+            return Err(CheckbookError::Migration(migration.version, error.to_string()));
         }
-        category_id
+
+        println!("Migrated database to version {}: {}", migration.version, migration.description);
+    }
+
+    Ok(())
+}
+
+fn table_exists(db: &Connection, table: &str) -> Result<bool, CheckbookError> {
+    let count: i32 = db.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = (?1)", [table], |row| row.get(0))?;
+
+    Ok(count > 0)
+}
+
+/// build a record from a row laid out like the ledger view.
+fn record_from_row(row: &Row) -> rusqlite::Result<Record> {
+    let id: String = row.get(0)?;
+
+    let date: String = row.get(1)?;
+    let check_number: u32 = row.get(2).unwrap_or_default();
+
+    let category: String = row.get(6).unwrap_or_default();
+
+    let vendor: String = row.get(4).unwrap_or_default();
+
+    let memo: String = row.get(5).unwrap_or_default();
+
+    let amount: i64 = row.get(7).unwrap_or_default();
+
+    let is_reconciled: String = if let Ok(r) = row.get(3) {
+        r
     } else {
-        category_id
+        "N".to_string()
+    };
+
+    let mut transaction = Transaction::builder()
+    .set_date(&date)
+    .set_check_number(check_number)
+    .set_category(&category)
+    .set_vendor(&vendor)
+    .set_memo(&memo)
+    .set_is_reconciled(is_reconciled.to_lowercase() == "y")
+    .build();
+
+    set_signed_amount(&mut transaction, from_minor_units(amount));
+
+    Ok(Record::from(&id, transaction))
+}
+
+/** retrieve the records in the register.
+ * When an account is given, only records in that account are retrieved.
+ */
+pub fn load_records_from_db(db: &Connection, account: &Option<String>) -> Result<Vec<Record>, CheckbookError> {
+    Ok(load_ledger_from_db(db, account)?.into_iter().map(|entry| entry.record).collect())
+}
+
+/** retrieve the records in the register, along with the running balance at each one.
+ * When an account is given, only records in that account are retrieved,
+ * and balances are for that account alone.
+ * Otherwise, balances are for every account combined.
+ */
+pub fn load_ledger_from_db(db: &Connection, account: &Option<String>) -> Result<Vec<LedgerEntry>, CheckbookError> {
+    if let Some(name) = account && !account_exists_in_db(db, name)? {
+        return Err(CheckbookError::AccountNotFound(name.to_string()));
     }
+
+    let mut statement = db.prepare("SELECT id, date, check_number, reconciled, vendor, memo, category, amount, CASE WHEN (?1) IS NULL THEN total_balance ELSE balance END FROM ledger WHERE (?1) IS NULL OR account = (?1) COLLATE NOCASE")?;
+
+    let entry_query = statement.query_map([account], |row| {
+        let status: String = row.get(3)?;
+        let balance: i64 = row.get(8)?;
+
+        Ok(LedgerEntry::from(record_from_row(row)?, from_minor_units(balance), Status::from_letter(&status)))
+    })?;
+
+    let stored_entries = entry_query.collect::<Result<Vec<LedgerEntry>, rusqlite::Error>>()?;
+
+    Ok(stored_entries)
 }
 
-pub fn add_category_to_db(p: &str, c: &str) {
-    if !category_exists_in_db(p, c) {
-        if let Ok(db) = Connection::open(&real_path(p)) {
-            let insert_query = format!("INSERT INTO categories(category) VALUES (?1)");
+pub fn load_categories_from_db(db: &Connection) -> Result<Vec<String>, CheckbookError> {
+    let mut statement = db.prepare_cached("SELECT category FROM categories")?;
 
-            if let Ok(mut statement) = db.prepare(&insert_query) {
-                if let Err(error) = statement.execute([c]) {
-                    println!("{}", error);
-                }
-            }
-        } else {}
+    let category_query = statement.query_map([], |row| row.get(0))?;
+
+    let stored_categories = category_query.collect::<Result<Vec<String>, rusqlite::Error>>()?;
+
+    Ok(stored_categories)
+}
+
+pub fn load_accounts_from_db(db: &Connection) -> Result<Vec<AccountDetails>, CheckbookError> {
+    let mut statement = db.prepare("SELECT a.name, a.closed, IFNULL(SUM(t.amount), 0) FROM accounts a LEFT JOIN trades t ON t.account = a.id GROUP BY a.id ORDER BY a.name")?;
+
+    let account_query = statement.query_map([], |row| {
+        let name: String = row.get(0)?;
+        let closed: i32 = row.get(1)?;
+        let balance: i64 = row.get(2)?;
+
+        Ok(AccountDetails::from(&name, closed == 1, from_minor_units(balance)))
+    })?;
+
+    let stored_accounts = account_query.collect::<Result<Vec<AccountDetails>, rusqlite::Error>>()?;
+
+    Ok(stored_accounts)
+}
+
+fn account_id(db: &Connection, a: &str) -> Result<Option<i32>, CheckbookError> {
+    let mut statement = db.prepare_cached("SELECT id FROM accounts WHERE name = (?1) COLLATE NOCASE")?;
+
+    Ok(statement.query_row([a], |row| row.get(0)).optional()?)
+}
+
+/// retrieve the identifier of an account that can still receive entries.
+fn open_account_id(db: &Connection, a: &str) -> Result<i32, CheckbookError> {
+    let mut statement = db.prepare_cached("SELECT id, name, closed FROM accounts WHERE name = (?1) COLLATE NOCASE")?;
+
+    let account: Option<(i32, String, i32)> = statement.query_row([a], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).optional()?;
+
+    match account {
+        Some((_, name, 1)) => Err(CheckbookError::AccountClosed(name)),
+        Some((id, _, _)) => Ok(id),
+        None => Err(CheckbookError::AccountNotFound(a.to_string()))
     }
 }
 
-pub fn add_record_to_db(p: &str, r: &Record) {
-    let category_id = if let Some(category) = &r.transaction.category {
-        if !category.is_empty() && category.to_lowercase() != "uncategorized" {
-            if let Some(id) = category_id(p, category) {
-                Some(id)
-            } else {
-                add_category_to_db(p, category);
-                category_id(p, &category)
-            }
-        } else {
-            None
-        }
-    } else {
-        None
+pub fn account_exists_in_db(db: &Connection, a: &str) -> Result<bool, CheckbookError> {
+    Ok(account_id(db, a)?.is_some())
+}
+
+pub fn add_account_to_db(db: &Connection, a: &str) -> Result<(), CheckbookError> {
+    if account_exists_in_db(db, a)? {
+        return Err(CheckbookError::AccountExists(a.to_string()));
+    }
+
+    db.execute("INSERT INTO accounts(name) VALUES (?1)", [a])?;
+
+    Ok(())
+}
+
+pub fn rename_account_in_db(db: &Connection, a: &str, n: &str) -> Result<(), CheckbookError> {
+    if !account_exists_in_db(db, a)? {
+        return Err(CheckbookError::AccountNotFound(a.to_string()));
+    } else if a.to_lowercase() != n.to_lowercase() && account_exists_in_db(db, n)? {
+        return Err(CheckbookError::AccountExists(n.to_string()));
+    }
+
+    db.execute("UPDATE accounts SET name = (?1) WHERE name = (?2) COLLATE NOCASE", [n, a])?;
+
+    Ok(())
+}
+
+pub fn close_account_in_db(db: &Connection, a: &str) -> Result<(), CheckbookError> {
+    if !account_exists_in_db(db, a)? {
+        return Err(CheckbookError::AccountNotFound(a.to_string()));
+    }
+
+    db.execute("UPDATE accounts SET closed = 1 WHERE name = (?1) COLLATE NOCASE", [a])?;
+
+    Ok(())
+}
+
+/** move an existing entry into a different account.
+ * Half of a transfer cannot be moved into the account the other half is in,
+ * since a transfer has to be between two accounts.
+ */
+pub fn move_record_to_account(db: &Connection, i: &str, a: &str) -> Result<(), CheckbookError> {
+    let account = open_account_id(db, a)?;
+    let other_account: Option<i32> = db.query_row("SELECT o.account FROM trades t JOIN trades o ON o.id = t.transfer WHERE t.id = (?1) COLLATE NOCASE", [i], |row| row.get(0)).optional()?;
+
+    if other_account == Some(account) {
+        return Err(CheckbookError::ConstraintViolation(format!("{} is half of a transfer, and the other half is already in {}.", i, a)));
+    }
+
+    db.execute("UPDATE trades SET account = (?1) WHERE id = (?2) COLLATE NOCASE", params![account, i])?;
+
+    Ok(())
+}
+
+pub fn category_exists_in_db(db: &Connection, c: &str) -> Result<bool, CheckbookError> {
+    Ok(category_id(db, c)?.is_some())
+}
+
+fn category_id(db: &Connection, c: &str) -> Result<Option<i32>, CheckbookError> {
+    let mut statement = db.prepare_cached("SELECT id FROM categories WHERE category = (?1) COLLATE NOCASE")?;
+
+    Ok(statement.query_row([normalized_path(c)], |row| row.get(0)).optional()?)
+}
+
+/** add a category to the register, along with any of its parents that are missing.
+ * Categories are nested with colons, so adding Utilities:Electric also adds Utilities.
+ */
+pub fn add_category_to_db(db: &Connection, c: &str) -> Result<(), CheckbookError> {
+    let path = normalized_path(c);
+
+    if !category_exists_in_db(db, &path)? {
+        let parent_id = match parent_path(&path) {
+            Some(parent) => {
+                add_category_to_db(db, parent)?;
+                category_id(db, parent)?
+            },
+            None => None
+        };
+
+        db.execute("INSERT INTO categories(category, parent) VALUES (?1, ?2)", params![path, parent_id])?;
+    }
+
+    Ok(())
+}
+
+/// check if any categories are nested directly under the given category.
+fn has_subcategories(db: &Connection, id: i32) -> Result<bool, CheckbookError> {
+    let count: u32 = db.query_row("SELECT COUNT(*) FROM categories WHERE parent = (?1)", [id], |row| row.get(0))?;
+
+    Ok(count > 0)
+}
+
+/** retrieve every category, along with how many entries and splits use it and their total.
+ * Entries with splits have no category of their own,
+ * so nothing is counted twice.
+ */
+pub fn load_category_details_from_db(db: &Connection) -> Result<Vec<CategoryDetails>, CheckbookError> {
+    let mut statement = db.prepare("SELECT c.category, (SELECT COUNT(*) FROM trades t WHERE t.category = c.id) + (SELECT COUNT(*) FROM splits s WHERE s.category = c.id), (SELECT IFNULL(SUM(t.amount), 0) FROM trades t WHERE t.category = c.id) + (SELECT IFNULL(SUM(s.amount), 0) FROM splits s WHERE s.category = c.id) FROM categories c")?;
+
+    let category_query = statement.query_map([], |row| {
+        let name: String = row.get(0)?;
+        let usage: u32 = row.get(1)?;
+        let total: i64 = row.get(2)?;
+
+        Ok(CategoryDetails::from(&name, usage, from_minor_units(total)))
+    })?;
+
+    let mut stored_categories = category_query.collect::<Result<Vec<CategoryDetails>, rusqlite::Error>>()?;
+    stored_categories.sort_by_key(|category| sort_key(&category.name));
+
+    Ok(stored_categories)
+}
+
+/** rename a category, along with every category nested under it.
+ * Giving a path, such as Utilities:Electric, moves the category under that parent,
+ * which is added if needed.
+ */
+pub fn rename_category_in_db(db: &Connection, c: &str, n: &str) -> Result<(), CheckbookError> {
+    let old_path = normalized_path(c);
+    let new_path = normalized_path(n);
+
+    let Some(id) = category_id(db, &old_path)? else {
+        return Err(CheckbookError::CategoryNotFound(c.to_string()));
+    };
+
+    if old_path.to_lowercase() != new_path.to_lowercase() && category_exists_in_db(db, &new_path)? {
+        return Err(CheckbookError::CategoryExists(n.to_string()));
+    } else if new_path.to_lowercase() != old_path.to_lowercase() && is_within(&new_path, &old_path) {
+        return Err(CheckbookError::InvalidInput(format!("category {} cannot be moved under itself.", c)));
+    }
+
+    let parent_id = match parent_path(&new_path) {
+        Some(parent) => {
+            add_category_to_db(db, parent)?;
+            category_id(db, parent)?
+        },
+        None => None
+    };
+
+    db.execute("UPDATE categories SET category = (?1) || SUBSTR(category, LENGTH(?2) + 1) WHERE SUBSTR(category, 1, LENGTH(?2) + 1) = (?2) || ':' COLLATE NOCASE", [&new_path, &old_path])?;
+    db.execute("UPDATE categories SET category = (?1), parent = (?2) WHERE id = (?3)", params![new_path, parent_id, id])?;
+
+    Ok(())
+}
+
+/** move every entry and split in one category into another, then remove the first category.
+ * Both categories must already exist.
+ */
+pub fn merge_category_in_db(db: &Connection, c: &str, into: &str) -> Result<(), CheckbookError> {
+    let Some(from_id) = category_id(db, c)? else {
+        return Err(CheckbookError::CategoryNotFound(c.to_string()));
     };
 
-    if let Ok(db) = Connection::open(&real_path(p)) {
-        let insert_statement = format!("INSERT INTO trades VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)");
+    let Some(into_id) = category_id(db, into)? else {
+        return Err(CheckbookError::CategoryNotFound(into.to_string()));
+    };
+
+    if from_id == into_id {
+        return Err(CheckbookError::InvalidInput("Please specify two different categories.".to_string()));
+    } else if has_subcategories(db, from_id)? {
+        return Err(CheckbookError::CategoryHasSubcategories(c.to_string()));
+    }
+
+    reassign_category_in_db(db, from_id, Some(into_id))?;
+    db.execute("DELETE FROM categories WHERE id = (?1)", [from_id])?;
 
-        if let Ok(mut statement) = db.prepare(&insert_statement) {
-            if let Err(error) = statement.execute(params![r.id.to_uppercase(),format!("{}", r.transaction.date.format("%Y-%m-%d")), r.transaction.check_number, r.transaction.vendor, r.transaction.memo, if let TransactionType::Deposit = r.transaction.transaction_type { r.transaction.amount.to_f64() } else { r.transaction.amount.to_f64()*-1.0 }, category_id, r.transaction.is_reconciled]) {
-                println!("{}", error);
+    Ok(())
+}
+
+/** remove a category from the register.
+ * If entries or splits still use the category, they are moved to the category given with reassign,
+ * and the category is only removed when one is given.
+ * Reassigning to Uncategorized leaves them without a category.
+ */
+pub fn delete_category_from_db(db: &Connection, c: &str, reassign: &Option<String>) -> Result<(), CheckbookError> {
+    let Some(details) = load_category_details_from_db(db)?.into_iter().find(|category| category.name.to_lowercase() == normalized_path(c).to_lowercase()) else {
+        return Err(CheckbookError::CategoryNotFound(c.to_string()));
+    };
+
+    match reassign {
+        Some(category) if category.to_lowercase() != "uncategorized" => merge_category_in_db(db, c, category),
+        None if details.usage > 0 => Err(CheckbookError::CategoryInUse(details.name, details.usage)),
+        _ => {
+            let Some(from_id) = category_id(db, c)? else {
+                return Err(CheckbookError::CategoryNotFound(c.to_string()));
+            };
+
+            if has_subcategories(db, from_id)? {
+                return Err(CheckbookError::CategoryHasSubcategories(c.to_string()));
             }
+
+            reassign_category_in_db(db, from_id, None)?;
+            db.execute("DELETE FROM categories WHERE id = (?1)", [from_id])?;
+
+            Ok(())
         }
-    } else {}
+    }
 }
 
-pub fn retrieve_record_with_id_from_db(p: &str, i: &str) -> Option<Record> {
-    let records = load_records_from_db(p);
-
-    let index_of_record = records.iter().position(|record| record.id.to_lowercase() == i.to_lowercase());
+/// point every entry, split, rule, schedule, and budget in one category at another, or at no category at all.
+fn reassign_category_in_db(db: &Connection, from: i32, to: Option<i32>) -> Result<(), CheckbookError> {
+    db.execute("UPDATE trades SET category = (?1) WHERE category = (?2)", params![to, from])?;
+    db.execute("UPDATE splits SET category = (?1) WHERE category = (?2)", params![to, from])?;
+    db.execute("UPDATE rules SET category = (?1) WHERE category = (?2)", params![to, from])?;
+    db.execute("UPDATE schedules SET category = (?1) WHERE category = (?2)", params![to, from])?;
 
-    match index_of_record {
-        Some(index) => Some(records[index].clone()),
-        _ => None
+    // budgets for the same month are added together, and budgets are dropped along with their category.
+    if let Some(to) = to {
+        db.execute("INSERT INTO budgets(category, period, amount) SELECT (?1), period, amount FROM budgets WHERE category = (?2) ON CONFLICT(category, period) DO UPDATE SET amount = amount + excluded.amount", params![to, from])?;
     }
+
+    db.execute("DELETE FROM budgets WHERE category = (?1)", [from])?;
+
+    Ok(())
 }
 
-pub fn update_record_in_db(p: &str, r: &Record) {
-    let category_id = if let Some(category) = &r.transaction.category {
-        if !category.is_empty() && category.to_lowercase() != "uncategorized" {
-            if let Some(id) = category_id(p, category) {
-                Some(id)
+/** retrieve the identifier for the given category, adding the category if needed.
+ * Empty and uncategorized categories have no identifier.
+ */
+fn stored_category_id(db: &Connection, c: &Option<String>) -> Result<Option<i32>, CheckbookError> {
+    if let Some(category) = c {
+        if !normalized_path(category).is_empty() && category.to_lowercase() != "uncategorized" {
+            if let Some(id) = category_id(db, category)? {
+                Ok(Some(id))
             } else {
-                add_category_to_db(p, category);
-                category_id(p, &category)
+                add_category_to_db(db, category)?;
+                category_id(db, category)
             }
         } else {
-            None
+            Ok(None)
         }
     } else {
-        None
+        Ok(None)
+    }
+}
+
+pub fn add_record_to_db(db: &Connection, r: &Record, account: &Option<String>) -> Result<(), CheckbookError> {
+    let account_id = match account {
+        Some(name) => open_account_id(db, name)?,
+        None => DEFAULT_ACCOUNT_ID
     };
 
-    if let Some(record) = retrieve_record_with_id_from_db(p, &r.id) {
-        if let Ok(db) = Connection::open(&real_path(p)) {
-            if r.transaction != record.transaction {
-                let update_statement = format!("UPDATE trades SET date = (?1), check_number = (?2), vendor = (?3), memo = (?4), amount = (?5), category = (?6), reconciled = (?7) WHERE id = (?8)");
+    let category_id = stored_category_id(db, &r.transaction.category)?;
+    let sequence = next_sequence(db)?;
 
-                if let Ok(mut statement) = db.prepare(&update_statement) {
-                    if let Err(error) = statement.execute(params![format!("{}", r.transaction.date.format("%Y-%m-%d")), r.transaction.check_number, r.transaction.vendor, r.transaction.memo, if let TransactionType::Deposit = r.transaction.transaction_type { r.transaction.amount.to_f64() } else { r.transaction.amount.to_f64()*-1.0 }, category_id, r.transaction.is_reconciled, record.id]) {
-                        println!("{}", error);
-                    }
-                }
-            }
+    let mut statement = db.prepare_cached("INSERT INTO trades(id, date, check_number, vendor, memo, amount, category, status, account, sequence) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?;
+
+    statement.execute(params![r.id.to_uppercase(),format!("{}", r.transaction.date.format("%Y-%m-%d")), r.transaction.check_number, r.transaction.vendor, r.transaction.memo, to_minor_units(signed_amount(&r.transaction))?, category_id, Status::from_reconciled(r.transaction.is_reconciled).to_string(), account_id, sequence])?;
+
+    Ok(())
+}
+
+/** retrieve the sequence number for a new entry.
+ * Entries on the same day are ordered by sequence,
+ * so new entries come after everything already stored.
+ */
+fn next_sequence(db: &Connection) -> Result<i64, CheckbookError> {
+    let mut statement = db.prepare_cached("SELECT IFNULL(MAX(sequence), 0) + 1 FROM trades")?;
+
+    Ok(statement.query_row([], |row| row.get(0))?)
+}
+
+/** move an entry to the given position among the entries on the same day.
+ * Positions start at 1, and positions past the last entry move it to the end of the day.
+ * The entries of the day swap sequence numbers among themselves,
+ * so entries on other days are not affected.
+ */
+pub fn move_record_within_day(db: &Connection, i: &str, position: usize) -> Result<(), CheckbookError> {
+    let Some(record) = retrieve_record_with_id_from_db(db, i)? else {
+        return Err(CheckbookError::RecordNotFound(i.to_string()));
+    };
+
+    let mut statement = db.prepare("SELECT id, sequence FROM trades WHERE DATE(date) = (?1) ORDER BY sequence")?;
+
+    let day_query = statement.query_map([format!("{}", record.transaction.date.format("%Y-%m-%d"))], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?;
+
+    let (mut ids, sequences): (Vec<String>, Vec<i64>) = day_query.collect::<Result<Vec<(String, i64)>, rusqlite::Error>>()?.into_iter().unzip();
+
+    if let Some(index) = ids.iter().position(|id| *id == record.id) {
+        let id = ids.remove(index);
+        ids.insert(position.saturating_sub(1).min(ids.len()), id);
+    }
+
+    let mut update_statement = db.prepare("UPDATE trades SET sequence = (?1) WHERE id = (?2)")?;
+
+    for (id, sequence) in ids.iter().zip(sequences) {
+        update_statement.execute(params![sequence, id])?;
+    }
+
+    Ok(())
+}
+
+/** retrieve a single record by its identifier.
+ * Identifiers are stored in uppercase, so the lookup can use the primary key,
+ * rather than reading through the whole ledger.
+ */
+pub fn retrieve_record_with_id_from_db(db: &Connection, i: &str) -> Result<Option<Record>, CheckbookError> {
+    let mut statement = db.prepare_cached("SELECT t.id, DATE(t.date), t.check_number, CASE t.status WHEN 'reconciled' THEN 'Y' WHEN 'cleared' THEN 'C' ELSE 'N' END, t.vendor, t.memo, c.category, t.amount FROM trades t LEFT JOIN categories c ON t.category = c.id WHERE t.id = UPPER(?1)")?;
+
+    Ok(statement.query_row([i], record_from_row).optional()?)
+}
+
+/** set the status of an entry.
+ * Entries only know whether they are reconciled,
+ * so this is the only way to mark an entry as cleared.
+ */
+pub fn set_record_status_in_db(db: &Connection, i: &str, status: &Status) -> Result<(), CheckbookError> {
+    let mut statement = db.prepare_cached("UPDATE trades SET status = (?1) WHERE id = UPPER(?2)")?;
+
+    if statement.execute(params![status.to_string(), i])? == 0 {
+        return Err(CheckbookError::RecordNotFound(i.to_string()));
+    }
+
+    Ok(())
+}
+
+/** retrieve the identifier of the entry in the given account with the given bank transaction identifier, if there is one.
+ * Banks only promise that identifiers are unique within an account, so other accounts are not checked.
+ * Exported OFX files use the identifier of each entry, so those entries are recognized too.
+ */
+pub fn record_id_with_fitid_in_db(db: &Connection, fitid: &str, account: &Option<String>) -> Result<Option<String>, CheckbookError> {
+    let account_id = match account {
+        Some(name) => account_id(db, name)?,
+        None => Some(DEFAULT_ACCOUNT_ID)
+    };
+
+    let Some(account_id) = account_id else {
+        return Ok(None);
+    };
+
+    let mut statement = db.prepare_cached("SELECT id FROM trades WHERE account = (?1) AND (fitid = (?2) OR id = UPPER(?2)) LIMIT 1")?;
+
+    Ok(statement.query_row(params![account_id, fitid], |row| row.get(0)).optional()?)
+}
+
+pub fn set_record_fitid_in_db(db: &Connection, i: &str, fitid: &str) -> Result<(), CheckbookError> {
+    let mut statement = db.prepare_cached("UPDATE trades SET fitid = (?1) WHERE id = UPPER(?2)")?;
+
+    if statement.execute(params![fitid, i])? == 0 {
+        return Err(CheckbookError::RecordNotFound(i.to_string()));
+    }
+
+    Ok(())
+}
+
+/** update an entry with the details of the given record.
+ * Cleared entries stay cleared, unless they are marked as reconciled.
+ */
+pub fn update_record_in_db(db: &Connection, r: &Record) -> Result<(), CheckbookError> {
+    let Some(record) = retrieve_record_with_id_from_db(db, &r.id)? else {
+        return Err(CheckbookError::RecordNotFound(r.id.clone()));
+    };
+
+    if r.transaction != record.transaction {
+        let category_id = stored_category_id(db, &r.transaction.category)?;
+
+        let mut statement = db.prepare_cached("UPDATE trades SET date = (?1), check_number = (?2), vendor = (?3), memo = (?4), amount = (?5), category = (?6), status = CASE WHEN (?7) THEN 'reconciled' WHEN status = 'reconciled' THEN 'uncleared' ELSE status END WHERE id = (?8)")?;
+
+        statement.execute(params![format!("{}", r.transaction.date.format("%Y-%m-%d")), r.transaction.check_number, r.transaction.vendor, r.transaction.memo, to_minor_units(signed_amount(&r.transaction))?, category_id, r.transaction.is_reconciled, record.id])?;
+
+        update_linked_transfer_in_db(db, r)?;
+    }
+
+    Ok(())
+}
+
+/** keep the other half of a transfer in step with the given record.
+ * The linked entry gets the same date, memo, and the opposite amount.
+ * Vendors are left alone, since each half names the account on the other side, such as Transfer to Savings.
+ */
+fn update_linked_transfer_in_db(db: &Connection, r: &Record) -> Result<(), CheckbookError> {
+    let amount = signed_amount(&r.transaction);
+
+    let mut statement = db.prepare_cached("UPDATE trades SET date = (?1), memo = (?2), amount = (?3) WHERE id = (SELECT transfer FROM trades WHERE id = UPPER(?4))")?;
+
+    statement.execute(params![format!("{}", r.transaction.date.format("%Y-%m-%d")), r.transaction.memo, to_minor_units(-amount)?, r.id])?;
+
+    Ok(())
+}
+
+/// link two entries already in the register as the halves of a transfer.
+pub fn link_transfer_in_db(db: &Connection, first: &str, second: &str) -> Result<(), CheckbookError> {
+    let mut statement = db.prepare_cached("UPDATE trades SET transfer = (?2) WHERE id = UPPER(?1)")?;
+
+    for (id, other_id) in [(first, second), (second, first)] {
+        if statement.execute(params![id, other_id.to_uppercase()])? == 0 {
+            return Err(CheckbookError::RecordNotFound(id.to_string()));
         }
     }
+
+    Ok(())
 }
 
-pub fn delete_record_from_db(p: &str, i: &str) {
-    if let Some(record) = retrieve_record_with_id_from_db(p, i) {
-        if let Ok(db) = Connection::open(&real_path(p)) {
-            let delete_statement = format!("DELETE FROM trades WHERE id = (?)");
+/** add both halves of a transfer between two accounts.
+ * The withdrawal and deposit should be written in the same transaction,
+ * so either both are stored or neither is.
+ */
+pub fn add_transfer_to_db(db: &Connection, withdrawal: &Record, from_account: &str, deposit: &Record, to_account: &str) -> Result<(), CheckbookError> {
+    let from_id = open_account_id(db, from_account)?;
+    let to_id = open_account_id(db, to_account)?;
+    let sequence = next_sequence(db)?;
 
-            if let Ok(mut statement) = db.prepare(&delete_statement) {
-                if let Err(error) = statement.execute(params![record.id]) {
-                    println!("{}", error);
-                }
-            }
+    let mut statement = db.prepare("INSERT INTO trades(id, date, vendor, memo, amount, status, account, transfer, sequence) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+
+    statement.execute(params![withdrawal.id.to_uppercase(), format!("{}", withdrawal.transaction.date.format("%Y-%m-%d")), withdrawal.transaction.vendor, withdrawal.transaction.memo, to_minor_units(-withdrawal.transaction.amount)?, Status::from_reconciled(withdrawal.transaction.is_reconciled).to_string(), from_id, deposit.id.to_uppercase(), sequence])?;
+
+    statement.execute(params![deposit.id.to_uppercase(), format!("{}", deposit.transaction.date.format("%Y-%m-%d")), deposit.transaction.vendor, deposit.transaction.memo, to_minor_units(deposit.transaction.amount)?, Status::from_reconciled(deposit.transaction.is_reconciled).to_string(), to_id, withdrawal.id.to_uppercase(), sequence + 1])?;
+
+    Ok(())
+}
+
+/// retrieve the splits of every entry, keyed by the lowercase identifier of the entry.
+pub fn load_splits_from_db(db: &Connection) -> Result<HashMap<String, Vec<Split>>, CheckbookError> {
+    let mut stored_splits: HashMap<String, Vec<Split>> = HashMap::new();
+
+    let mut statement = db.prepare("SELECT s.trade, c.category, s.amount, s.memo FROM splits s LEFT JOIN categories c ON s.category = c.id ORDER BY s.id")?;
+
+    let split_query = statement.query_map([], |row| {
+        let trade: String = row.get(0)?;
+        let category: String = row.get(1).unwrap_or(String::default());
+        let amount: i64 = row.get(2)?;
+        let memo: String = row.get(3).unwrap_or(String::default());
+
+        Ok((trade, Split::from(&category, from_minor_units(amount), &memo)))
+    })?;
+
+    for split in split_query {
+        let (trade, split) = split?;
+        stored_splits.entry(trade.to_lowercase()).or_default().push(split);
+    }
+
+    Ok(stored_splits)
+}
+
+/** replace the splits of the given entry.
+ * Entries with splits have no category of their own,
+ * so the category of the entry is cleared when splits are given.
+ */
+pub fn replace_splits_in_db(db: &Connection, i: &str, splits: &Vec<Split>) -> Result<(), CheckbookError> {
+    let Some(record) = retrieve_record_with_id_from_db(db, i)? else {
+        return Err(CheckbookError::RecordNotFound(i.to_string()));
+    };
+
+    db.prepare_cached("DELETE FROM splits WHERE trade = (?1)")?.execute([&record.id])?;
+
+    if !splits.is_empty() {
+        db.prepare_cached("UPDATE trades SET category = NULL WHERE id = (?1)")?.execute([&record.id])?;
+
+        for split in splits {
+            let category_id = stored_category_id(db, &split.category)?;
+
+            db.prepare_cached("INSERT INTO splits(trade, category, amount, memo) VALUES (?1, ?2, ?3, ?4)")?.execute(params![record.id, category_id, to_minor_units(split.amount)?, split.memo])?;
         }
     }
+
+    Ok(())
+}
+
+/// retrieve the identifiers of every entry that is half of a transfer.
+pub fn load_transfer_ids_from_db(db: &Connection) -> Result<Vec<String>, CheckbookError> {
+    let mut statement = db.prepare("SELECT id FROM trades WHERE transfer IS NOT NULL")?;
+
+    let transfer_query = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+    let transfer_ids = transfer_query.map(|id| id.map(|id| id.to_lowercase())).collect::<Result<Vec<String>, rusqlite::Error>>()?;
+
+    Ok(transfer_ids)
+}
+
+/// retrieve the name of the account each entry is in, keyed by the lowercase identifier of the entry.
+pub fn load_record_accounts_from_db(db: &Connection) -> Result<HashMap<String, String>, CheckbookError> {
+    let mut statement = db.prepare("SELECT t.id, a.name FROM trades t JOIN accounts a ON t.account = a.id")?;
+
+    let account_query = statement.query_map([], |row| Ok((row.get::<_, String>(0)?.to_lowercase(), row.get::<_, String>(1)?)))?;
+
+    let record_accounts = account_query.collect::<Result<HashMap<String, String>, rusqlite::Error>>()?;
+
+    Ok(record_accounts)
+}
+
+/// delete the given record, along with the other half of a transfer.
+pub fn delete_record_from_db(db: &Connection, i: &str) -> Result<(), CheckbookError> {
+    let Some(record) = retrieve_record_with_id_from_db(db, i)? else {
+        return Err(CheckbookError::RecordNotFound(i.to_string()));
+    };
+
+    db.execute("DELETE FROM splits WHERE trade IN (SELECT id FROM trades WHERE id = (?1) OR transfer = (?1))", params![record.id])?;
+    db.execute("DELETE FROM trades WHERE id = (?1) OR transfer = (?1)", params![record.id])?;
+
+    Ok(())
 }
 
-pub fn update_or_add_record(p: &str, r: &Record) {
-    if let Some(_) = retrieve_record_with_id_from_db(p, &r.id) {
-        update_record_in_db(p, r);
+/** update the given record if it already exists, or add it otherwise.
+ * New records are placed in the given account,
+ * while existing ones stay where they are.
+ */
+pub fn update_or_add_record(db: &Connection, r: &Record, account: &Option<String>) -> Result<(), CheckbookError> {
+    if retrieve_record_with_id_from_db(db, &r.id)?.is_some() {
+        update_record_in_db(db, r)
     } else {
-        add_record_to_db(p, r);
+        add_record_to_db(db, r, account)
     }
 }
 
-pub fn add_records_to_db(p: &str, r: &Vec<Record>) {
+/** add or update each of the given records.
+ * Imports are expected to run this in a single transaction,
+ * so a failure partway through leaves the register as it was.
+ */
+pub fn add_records_to_db(db: &Connection, r: &[Record], account: &Option<String>) -> Result<(), CheckbookError> {
     for (index, record) in r.iter().enumerate() {
         println!("Importing entry {} of {}", index+1, r.len());
-        update_or_add_record(p, record);
+        update_or_add_record(db, record, account)?;
+    }
+
+    Ok(())
+}
+
+/// retrieve every rule, in the order they were added.
+pub fn load_rules_from_db(db: &Connection) -> Result<Vec<Rule>, CheckbookError> {
+    let mut statement = db.prepare("SELECT r.id, r.vendor_pattern, r.memo_pattern, r.regex, r.minimum, r.maximum, r.transaction_type, c.category, r.vendor, r.memo FROM rules r LEFT JOIN categories c ON r.category = c.id ORDER BY r.id")?;
+
+    let rule_query = statement.query_map([], |row| {
+        let is_regex: bool = row.get(3)?;
+        let pattern = |index: usize| -> Result<Option<Pattern>, rusqlite::Error> {
+            row.get::<_, Option<String>>(index)?.map(|text| Pattern::from(&text, is_regex).map_err(|error| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(error)))).transpose()
+        };
+
+        Ok(Rule {
+            id: row.get(0)?,
+            vendor_pattern: pattern(1)?,
+            memo_pattern: pattern(2)?,
+            minimum: row.get::<_, Option<i64>>(4)?.map(from_minor_units),
+            maximum: row.get::<_, Option<i64>>(5)?.map(from_minor_units),
+            transaction_type: row.get::<_, Option<String>>(6)?.and_then(|transaction_type| transaction_type.parse::<TransactionType>().ok()),
+            category: row.get(7)?,
+            vendor: row.get(8)?,
+            memo: row.get(9)?
+        })
+    })?;
+
+    let stored_rules = rule_query.collect::<Result<Vec<Rule>, rusqlite::Error>>()?;
+
+    Ok(stored_rules)
+}
+
+/** add a rule to the register, adding its category if needed.
+ * The patterns of a rule are either both regular expressions or both plain text.
+ */
+pub fn add_rule_to_db(db: &Connection, rule: &Rule) -> Result<(), CheckbookError> {
+    let category_id = stored_category_id(db, &rule.category)?;
+    let is_regex = rule.vendor_pattern.as_ref().is_some_and(Pattern::is_regex) || rule.memo_pattern.as_ref().is_some_and(Pattern::is_regex);
+    let transaction_type = rule.transaction_type.as_ref().map(|transaction_type| if let TransactionType::Deposit = transaction_type { "deposit" } else { "withdrawal" });
+
+    db.execute("INSERT INTO rules(vendor_pattern, memo_pattern, regex, minimum, maximum, transaction_type, category, vendor, memo) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)", params![rule.vendor_pattern.as_ref().map(Pattern::as_str), rule.memo_pattern.as_ref().map(Pattern::as_str), is_regex, rule.minimum.map(to_minor_units).transpose()?, rule.maximum.map(to_minor_units).transpose()?, transaction_type, category_id, rule.vendor, rule.memo])?;
+
+    Ok(())
+}
+
+pub fn delete_rule_from_db(db: &Connection, id: u32) -> Result<(), CheckbookError> {
+    if db.execute("DELETE FROM rules WHERE id = (?1)", [id])? == 0 {
+        return Err(CheckbookError::RuleNotFound(id));
+    }
+
+    Ok(())
+}
+
+/// retrieve every schedule, in the order they were added.
+pub fn load_schedules_from_db(db: &Connection) -> Result<Vec<ScheduledEntry>, CheckbookError> {
+    let mut statement = db.prepare("SELECT s.id, s.vendor, s.memo, s.amount, c.category, a.name, s.recurrence, s.day, s.start_date, s.last_posted FROM schedules s LEFT JOIN categories c ON s.category = c.id LEFT JOIN accounts a ON s.account = a.id ORDER BY s.id")?;
+
+    let schedule_query = statement.query_map([], scheduled_entry_from_row)?;
+
+    let stored_schedules = schedule_query.collect::<Result<Vec<ScheduledEntry>, rusqlite::Error>>()?;
+
+    Ok(stored_schedules)
+}
+
+pub fn retrieve_schedule_from_db(db: &Connection, id: u32) -> Result<ScheduledEntry, CheckbookError> {
+    load_schedules_from_db(db)?.into_iter().find(|schedule| schedule.id == id).ok_or(CheckbookError::ScheduleNotFound(id))
+}
+
+fn scheduled_entry_from_row(row: &Row) -> Result<ScheduledEntry, rusqlite::Error> {
+    let date = |index: usize| -> Result<Option<NaiveDate>, rusqlite::Error> {
+        row.get::<_, Option<String>>(index)?.map(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|error| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(error)))).transpose()
+    };
+
+    let recurrence: String = row.get(6)?;
+    let amount: i64 = row.get(3)?;
+
+    Ok(ScheduledEntry {
+        id: row.get(0)?,
+        vendor: row.get(1)?,
+        memo: row.get(2).unwrap_or_default(),
+        amount: from_minor_units(amount),
+        category: row.get(4)?,
+        account: row.get(5)?,
+        recurrence: Recurrence::from_str(&recurrence, true).map_err(|_| rusqlite::Error::InvalidColumnType(6, "recurrence".to_string(), rusqlite::types::Type::Text))?,
+        day: row.get(7)?,
+        start: date(8)?.unwrap_or_default(),
+        last_posted: date(9)?
+    })
+}
+
+/// add a schedule to the register, adding its category if needed.
+pub fn add_schedule_to_db(db: &Connection, schedule: &ScheduledEntry) -> Result<(), CheckbookError> {
+    let account_id = match &schedule.account {
+        Some(name) => open_account_id(db, name)?,
+        None => DEFAULT_ACCOUNT_ID
+    };
+
+    let category_id = stored_category_id(db, &schedule.category)?;
+
+    db.execute("INSERT INTO schedules(vendor, memo, amount, category, account, recurrence, day, start_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", params![schedule.vendor, schedule.memo, to_minor_units(schedule.amount)?, category_id, account_id, schedule.recurrence.to_string(), schedule.day, schedule.start.format("%Y-%m-%d").to_string()])?;
+
+    Ok(())
+}
+
+/// replace the details of a schedule, leaving the date it was last posted alone.
+pub fn update_schedule_in_db(db: &Connection, schedule: &ScheduledEntry) -> Result<(), CheckbookError> {
+    let account_id = match &schedule.account {
+        Some(name) => open_account_id(db, name)?,
+        None => DEFAULT_ACCOUNT_ID
+    };
+
+    let category_id = stored_category_id(db, &schedule.category)?;
+
+    if db.execute("UPDATE schedules SET vendor = (?1), memo = (?2), amount = (?3), category = (?4), account = (?5), recurrence = (?6), day = (?7), start_date = (?8) WHERE id = (?9)", params![schedule.vendor, schedule.memo, to_minor_units(schedule.amount)?, category_id, account_id, schedule.recurrence.to_string(), schedule.day, schedule.start.format("%Y-%m-%d").to_string(), schedule.id])? == 0 {
+        return Err(CheckbookError::ScheduleNotFound(schedule.id));
+    }
+
+    Ok(())
+}
+
+pub fn delete_schedule_from_db(db: &Connection, id: u32) -> Result<(), CheckbookError> {
+    if db.execute("DELETE FROM schedules WHERE id = (?1)", [id])? == 0 {
+        return Err(CheckbookError::ScheduleNotFound(id));
+    }
+
+    Ok(())
+}
+
+/** add an entry for every occurrence of the schedule through the given date that has not been posted yet.
+ * The last date posted is remembered, so posting again adds nothing new.
+ */
+pub fn post_schedule_to_db(db: &Connection, schedule: &ScheduledEntry, through: NaiveDate) -> Result<Vec<Record>, CheckbookError> {
+    let records: Vec<Record> = schedule.due_dates(through).into_iter().map(|date| schedule.record("", date)).collect();
+
+    for record in &records {
+        add_record_to_db(db, record, &schedule.account)?;
+    }
+
+    if let Some(last) = records.last() {
+        db.execute("UPDATE schedules SET last_posted = (?1) WHERE id = (?2)", params![last.transaction.date.format("%Y-%m-%d").to_string(), schedule.id])?;
+    }
+
+    Ok(records)
+}
+
+/// retrieve every budget, ordered by month and category.
+pub fn load_budgets_from_db(db: &Connection) -> Result<Vec<MonthlyBudget>, CheckbookError> {
+    let mut statement = db.prepare("SELECT c.category, b.period, b.amount FROM budgets b JOIN categories c ON b.category = c.id ORDER BY b.period, c.category COLLATE NOCASE")?;
+
+    let budget_query = statement.query_map([], |row| {
+        let category: String = row.get(0)?;
+        let period: String = row.get(1)?;
+        let amount: i64 = row.get(2)?;
+
+        let month = NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d").map_err(|error| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(error)))?;
+
+        Ok(MonthlyBudget::from(&category, month, from_minor_units(amount)))
+    })?;
+
+    let stored_budgets = budget_query.collect::<Result<Vec<MonthlyBudget>, rusqlite::Error>>()?;
+
+    Ok(stored_budgets)
+}
+
+/// set the budget of a category for the month that the given date is in, adding the category if needed.
+pub fn set_budget_in_db(db: &Connection, category: &str, month: NaiveDate, amount: D64) -> Result<(), CheckbookError> {
+    let Some(category_id) = stored_category_id(db, &Some(category.to_string()))? else {
+        return Err(CheckbookError::InvalidInput("Please give a category to budget for.".to_string()));
+    };
+
+    db.execute("INSERT INTO budgets(category, period, amount) VALUES (?1, ?2, ?3) ON CONFLICT(category, period) DO UPDATE SET amount = excluded.amount", params![category_id, month.format("%Y-%m").to_string(), to_minor_units(amount)?])?;
+
+    Ok(())
+}
+
+/** copy every budget from one month into another, returning how many were copied.
+ * Budgets that were already set for the later month are kept.
+ */
+pub fn copy_budgets_in_db(db: &Connection, from: NaiveDate, to: NaiveDate) -> Result<usize, CheckbookError> {
+    let copied = db.execute("INSERT OR IGNORE INTO budgets(category, period, amount) SELECT category, (?2), amount FROM budgets WHERE period = (?1)", params![from.format("%Y-%m").to_string(), to.format("%Y-%m").to_string()])?;
+
+    Ok(copied)
+}
+
+/// mark each of the given entries as reconciled.
+pub fn mark_records_reconciled_in_db(db: &Connection, ids: &[String]) -> Result<(), CheckbookError> {
+    let mut statement = db.prepare_cached("UPDATE trades SET status = 'reconciled' WHERE id = UPPER(?1)")?;
+
+    for id in ids {
+        if statement.execute([id])? == 0 {
+            return Err(CheckbookError::RecordNotFound(id.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// retrieve every completed reconciliation, from the oldest statement to the newest.
+pub fn load_reconciliations_from_db(db: &Connection) -> Result<Vec<Reconciliation>, CheckbookError> {
+    let mut statement = db.prepare("SELECT r.id, a.name, r.statement_date, r.statement_balance, r.reconciled_on, r.entries FROM reconciliations r LEFT JOIN accounts a ON r.account = a.id ORDER BY r.statement_date, r.id")?;
+
+    let reconciliation_query = statement.query_map([], |row| {
+        let date = |index: usize| -> Result<NaiveDate, rusqlite::Error> {
+            NaiveDate::parse_from_str(&row.get::<_, String>(index)?, "%Y-%m-%d").map_err(|error| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(error)))
+        };
+
+        let statement_balance: i64 = row.get(3)?;
+
+        Ok(Reconciliation {
+            id: row.get(0)?,
+            account: row.get(1)?,
+            statement_date: date(2)?,
+            statement_balance: from_minor_units(statement_balance),
+            reconciled_on: date(4)?,
+            entries: row.get(5)?
+        })
+    })?;
+
+    let stored_reconciliations = reconciliation_query.collect::<Result<Vec<Reconciliation>, rusqlite::Error>>()?;
+
+    Ok(stored_reconciliations)
+}
+
+/// record a completed reconciliation.
+pub fn add_reconciliation_to_db(db: &Connection, reconciliation: &Reconciliation) -> Result<(), CheckbookError> {
+    let account_id = match &reconciliation.account {
+        Some(account) => Some(account_id(db, account)?.ok_or(CheckbookError::AccountNotFound(account.clone()))?),
+        None => None
+    };
+
+    db.execute("INSERT INTO reconciliations(account, statement_date, statement_balance, reconciled_on, entries) VALUES (?1, ?2, ?3, ?4, ?5)", params![account_id, reconciliation.statement_date.format("%Y-%m-%d").to_string(), to_minor_units(reconciliation.statement_balance)?, reconciliation.reconciled_on.format("%Y-%m-%d").to_string(), reconciliation.entries])?;
+
+    Ok(())
+}
+
+pub fn load_csv_profiles_from_db(db: &Connection) -> Result<Vec<CsvProfile>, CheckbookError> {
+    let mut statement = db.prepare("SELECT name, columns, date_format, delimiter, skip_rows, sign, decimal_separator FROM csv_profiles ORDER BY name")?;
+
+    let profile_query = statement.query_map([], |row| {
+        let columns: String = row.get(1)?;
+        let delimiter: String = row.get(3)?;
+        let sign: String = row.get(5)?;
+        let decimal_separator: String = row.get(6)?;
+
+        Ok(CsvProfile {
+            name: row.get(0)?,
+            columns: CsvColumns::parse(&columns).map_err(|error| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(error)))?,
+            date_format: row.get(2)?,
+            delimiter: delimiter.chars().next().unwrap_or(','),
+            skip_rows: row.get(4)?,
+            sign: SignConvention::from_str(&sign, true).map_err(|_| rusqlite::Error::InvalidColumnType(5, "sign".to_string(), rusqlite::types::Type::Text))?,
+            decimal_separator: decimal_separator.chars().next().unwrap_or('.')
+        })
+    })?;
+
+    let stored_profiles = profile_query.collect::<Result<Vec<CsvProfile>, rusqlite::Error>>()?;
+
+    Ok(stored_profiles)
+}
+
+pub fn retrieve_csv_profile_from_db(db: &Connection, name: &str) -> Result<CsvProfile, CheckbookError> {
+    load_csv_profiles_from_db(db)?.into_iter().find(|profile| profile.name.to_lowercase() == name.to_lowercase()).ok_or(CheckbookError::ProfileNotFound(name.to_string()))
+}
+
+/// save a profile, replacing any profile already saved under the same name.
+pub fn save_csv_profile_to_db(db: &Connection, profile: &CsvProfile) -> Result<(), CheckbookError> {
+    db.execute("INSERT INTO csv_profiles(name, columns, date_format, delimiter, skip_rows, sign, decimal_separator) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) ON CONFLICT(name) DO UPDATE SET columns = excluded.columns, date_format = excluded.date_format, delimiter = excluded.delimiter, skip_rows = excluded.skip_rows, sign = excluded.sign, decimal_separator = excluded.decimal_separator", params![profile.name, profile.columns.to_string(), profile.date_format, profile.delimiter.to_string(), profile.skip_rows, profile.sign.to_string(), profile.decimal_separator.to_string()])?;
+
+    Ok(())
+}
+
+pub fn delete_csv_profile_from_db(db: &Connection, name: &str) -> Result<(), CheckbookError> {
+    if db.execute("DELETE FROM csv_profiles WHERE name = (?1)", [name])? == 0 {
+        return Err(CheckbookError::ProfileNotFound(name.to_string()));
     }
+
+    Ok(())
 }
 
-fn file_content(p: &str) -> Result<String, io::Error> {
-    let mut file_content = String::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the schema databases were created with before versions were recorded.
+    const BASELINE_SCHEMA: &str = include_str!("../tests/fixtures/register_v0.sql");
+
+    #[test]
+    fn migrates_baseline_database() {
+        let directory = env::temp_dir().join(format!("rcheckbook-migration-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+
+        fs::create_dir_all(&directory).expect("could not create test directory");
+
+        let baseline = Connection::open(&path).expect("could not create baseline database");
+        baseline.execute_batch(BASELINE_SCHEMA).expect("could not create baseline schema");
+        baseline.execute_batch("INSERT INTO trades (id, date, vendor, amount, category, reconciled) VALUES ('A', '2024-01-01', 'Opening', 1000.1, 7, 1), ('B', '2024-01-02', 'Market', -45.2, 3, 0), ('C', '2024-01-03', 'Cafe', -0.07, 4, 0);").expect("could not add entries");
+        drop(baseline);
+
+        let mut db = connect_to_database(p).expect("could not open baseline database");
+        migrate_database(&mut db, p).expect("could not migrate baseline database");
+
+        let version: i32 = db.query_row("PRAGMA user_version", [], |row| row.get(0)).expect("could not read version");
+        let amounts: Vec<i64> = db.prepare("SELECT amount FROM trades ORDER BY id").and_then(|mut statement| statement.query_map([], |row| row.get(0))?.collect()).expect("could not read amounts");
+        let balances: Vec<String> = load_ledger_from_db(&db, &None).expect("could not load ledger").iter().map(|entry| format!("{:.2}", entry.balance)).collect();
+
+        let foreign_keys: i32 = db.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).expect("could not read foreign key setting");
+
+        assert_eq!(version, latest_version());
+        assert_eq!(foreign_keys, 1);
+        assert_eq!(amounts, vec![100010, -4520, -7]);
+        assert_eq!(balances, vec!["1000.10", "954.90", "954.83"]);
+        assert!(directory.join("register.db.v0.bak").exists());
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+
+    #[test]
+    fn restores_foreign_keys_after_failed_migration() {
+        let directory = env::temp_dir().join(format!("rcheckbook-failed-migration-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+
+        fs::create_dir_all(&directory).expect("could not create test directory");
+
+        // a database claiming to be version 1 without any tables cannot be migrated.
+        let broken = Connection::open(&path).expect("could not create database");
+        broken.execute_batch("PRAGMA user_version = 1;").expect("could not set version");
+        drop(broken);
 
-    File::open(p)?.read_to_string(&mut file_content)?;
+        let mut db = connect_to_database(p).expect("could not open database");
+        let result = migrate_database(&mut db, p);
 
-    Ok(file_content)
-}
\ No newline at end of file
+        let foreign_keys: i32 = db.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).expect("could not read foreign key setting");
+
+        assert!(matches!(result, Err(CheckbookError::Migration(2, _))));
+        assert_eq!(foreign_keys, 1);
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+
+    #[test]
+    fn rejects_dangling_references_after_migrating() {
+        let directory = env::temp_dir().join(format!("rcheckbook-dangling-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+
+        // a split left behind by an entry removed while foreign keys were off, in a database that still needs the last migration.
+        let db = copy_database_if_not_exists(p).expect("could not create database");
+        db.execute_batch("PRAGMA foreign_keys = OFF; INSERT INTO splits(trade, amount) VALUES ('MISSING', 100); DROP TABLE csv_profiles;").expect("could not add split");
+        db.pragma_update(None, "user_version", latest_version() - 1).expect("could not set version");
+        drop(db);
+
+        let mut db = connect_to_database(p).expect("could not open database");
+
+        assert!(matches!(migrate_database(&mut db, p), Err(CheckbookError::Migration(_, _))));
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+
+    #[test]
+    fn opens_read_only_without_creating_or_migrating() {
+        let directory = env::temp_dir().join(format!("rcheckbook-read-only-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+
+        fs::create_dir_all(&directory).expect("could not create test directory");
+
+        assert!(matches!(open_database_read_only(p), Err(CheckbookError::DatabaseUnreadable(_))));
+        assert!(!path.exists());
+
+        let baseline = Connection::open(&path).expect("could not create baseline database");
+        baseline.execute_batch(BASELINE_SCHEMA).expect("could not create baseline schema");
+        drop(baseline);
+
+        assert!(matches!(open_database_read_only(p), Err(CheckbookError::DatabaseUnreadable(_))));
+
+        let db = connect_to_database(p).expect("could not open baseline database");
+
+        assert_eq!(database_version(&db).expect("could not read version"), 0);
+        assert!(!directory.join("register.db.v0.bak").exists());
+
+        drop(db);
+        fs::remove_file(&path).expect("could not remove baseline database");
+        drop(copy_database_if_not_exists(p).expect("could not create database"));
+
+        let db = open_database_read_only(p).expect("could not open database");
+
+        assert!(db.execute_batch("INSERT INTO categories(category) VALUES ('Travel')").is_err());
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+
+    #[test]
+    fn keeps_transfers_between_two_accounts() {
+        let directory = env::temp_dir().join(format!("rcheckbook-transfer-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+
+        let db = copy_database_if_not_exists(p).expect("could not create database");
+        add_account_to_db(&db, "Savings").expect("could not add account");
+
+        let half = |vendor: &str, amount: i32| {
+            let mut transaction = Transaction::builder().set_date("2026-10-05").set_vendor(vendor).set_memo("Emergency fund").build();
+            set_signed_amount(&mut transaction, D64::from(amount));
+            transaction
+        };
+
+        let withdrawal = Record::from("W", half("Transfer to Savings", -100));
+        let deposit = Record::from("D", half("Transfer from Default", 100));
+
+        add_transfer_to_db(&db, &withdrawal, "Default", &deposit, "Savings").expect("could not add transfer");
+
+        assert!(matches!(move_record_to_account(&db, "W", "Savings"), Err(CheckbookError::ConstraintViolation(_))));
+
+        let mut updated = withdrawal.clone();
+        updated.transaction.memo = "Vacation".to_string();
+        set_signed_amount(&mut updated.transaction, D64::from(-150));
+
+        update_record_in_db(&db, &updated).expect("could not update withdrawal");
+
+        let deposit = retrieve_record_with_id_from_db(&db, "D").expect("could not retrieve deposit").expect("deposit is missing");
+
+        assert_eq!(deposit.transaction.memo, "Vacation");
+        assert_eq!(deposit.transaction.vendor, "Transfer from Default");
+        assert_eq!(format!("{:.2}", signed_amount(&deposit.transaction)), "150.00");
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+}
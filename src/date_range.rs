@@ -32,10 +32,6 @@ impl DateRange {
         let from_datetime = Local.from_local_datetime(&self.from_date.and_hms_opt(0, 0, 0).unwrap()).unwrap();
         let to_datetime = Local.from_local_datetime(&self.to_date.and_hms_opt(0, 0, 0).unwrap()).unwrap();
         let query_datetime = Local.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).unwrap();
-        if query_datetime.timestamp() >= from_datetime.timestamp() && query_datetime.timestamp() <= to_datetime.timestamp() {
-            true
-        } else {
-            false
-        }
+        query_datetime.timestamp() >= from_datetime.timestamp() && query_datetime.timestamp() <= to_datetime.timestamp()
     }
 }
\ No newline at end of file
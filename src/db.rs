@@ -0,0 +1,64 @@
+use clap::Parser;
+use crate::{ database::*, errors::CheckbookError, migrations::latest_version };
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage the register database itself. \r\n\r\nThe schema of a database is versioned, and older databases are migrated automatically when they are opened. \r\n\r\nTo see what version a database is, do this: \r\n\r\nrcheckbook db version \r\n\r\nTo migrate a database without doing anything else, do this: \r\n\r\nrcheckbook db migrate \r\n\r\nA copy of the database is saved next to it before migrating, with the old version in its name.")]
+pub struct Db {
+    #[clap(subcommand)]
+    pub subcommand: DbCommand
+}
+
+#[derive(Parser)]
+pub enum DbCommand {
+    Migrate(MigrateDb),
+    Version(DbVersion)
+}
+
+#[derive(Parser)]
+#[clap(about = "Bring the database up to the latest version of the schema.")]
+pub struct MigrateDb {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Display the schema version of the database.")]
+pub struct DbVersion {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+impl Db {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            DbCommand::Migrate(m) => m.run(),
+            DbCommand::Version(v) => v.run()
+        }
+    }
+}
+
+impl MigrateDb {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = connect_to_database(&self.file_path)?;
+        let version = database_version(&db)?;
+
+        if version == latest_version() {
+            println!("Database is already at version {}.", version);
+            Ok(())
+        } else {
+            migrate_database(&mut db, &self.file_path)
+        }
+    }
+}
+
+impl DbVersion {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = connect_to_database(&self.file_path)?;
+        let version = database_version(&db)?;
+
+        println!("Database version\t{}", version);
+        println!("Latest version\t{}", latest_version());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::format::{ Item, StrftimeItems };
+use csv::{ QuoteStyle, Terminator, WriterBuilder };
+use fastnum::D64;
+use std::io;
+use crate::{ amount::signed_amount, errors::CheckbookError, export_column::ExportColumn, ledger_entry::LedgerEntry, sign_convention::SignConvention };
+
+/// the columns of a TSV export, in the same order as the list command, without the balance.
+pub const TSV_COLUMNS: [ExportColumn; 9] = [ExportColumn::Id, ExportColumn::Date, ExportColumn::Check, ExportColumn::Status, ExportColumn::Category, ExportColumn::Vendor, ExportColumn::Memo, ExportColumn::Credit, ExportColumn::Withdrawal];
+
+/// the columns of a CSV export, which are those of the spreadsheet exports.
+pub const CSV_COLUMNS: [ExportColumn; 10] = [ExportColumn::Id, ExportColumn::Date, ExportColumn::Check, ExportColumn::Status, ExportColumn::Category, ExportColumn::Vendor, ExportColumn::Memo, ExportColumn::Credit, ExportColumn::Withdrawal, ExportColumn::Balance];
+
+/** Represents how entries are laid out in a CSV or TSV export.
+ * Fields are quoted as RFC 4180 describes,
+ * so vendors and memos containing the delimiter, quotes, or line breaks are kept whole.
+ */
+pub struct DelimitedLayout {
+    pub columns: Vec<ExportColumn>,
+    pub has_header: bool,
+    pub delimiter: char,
+    pub date_format: String,
+    pub decimal_separator: char,
+    pub sign: SignConvention
+}
+
+impl DelimitedLayout {
+    /// present entries as delimited text, with a line for each entry ending in CRLF.
+    pub fn entries_to_text(&self, entries: &[LedgerEntry]) -> Result<String, CheckbookError> {
+        let delimiter = u8::try_from(self.delimiter).map_err(|_| CheckbookError::InvalidInput(format!("{} cannot be used as a delimiter. Please use a single ASCII character.", self.delimiter)))?;
+
+        let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .quote_style(QuoteStyle::Necessary)
+        .terminator(Terminator::CRLF)
+        .from_writer(vec![]);
+
+        if StrftimeItems::new(&self.date_format).any(|item| item == Item::Error) {
+            return Err(CheckbookError::InvalidInput(format!("{} is not a valid date format.", self.date_format)));
+        }
+
+        let write_error = |error: csv::Error| CheckbookError::Io(io::Error::other(error.to_string()));
+
+        if self.has_header {
+            writer.write_record(self.columns.iter().map(|column| column.heading())).map_err(write_error)?;
+        }
+
+        for entry in entries {
+            writer.write_record(self.columns.iter().map(|column| self.field(entry, column))).map_err(write_error)?;
+        }
+
+        let text = writer.into_inner().map_err(|error| CheckbookError::Io(io::Error::other(error.to_string())))?;
+
+        Ok(String::from_utf8_lossy(&text).to_string())
+    }
+
+    fn field(&self, entry: &LedgerEntry, column: &ExportColumn) -> String {
+        let transaction = &entry.record.transaction;
+        let amount = signed_amount(transaction);
+
+        match column {
+            ExportColumn::Id => entry.record.id.clone(),
+            ExportColumn::Date => transaction.date.format(&self.date_format).to_string(),
+            ExportColumn::Check => transaction.check_number.filter(|check_number| *check_number > 0).map(|check_number| check_number.to_string()).unwrap_or_default(),
+            ExportColumn::Status => entry.status.letter().to_string(),
+            ExportColumn::Category => transaction.category.clone().unwrap_or_default(),
+            ExportColumn::Vendor => transaction.vendor.clone(),
+            ExportColumn::Memo => transaction.memo.clone(),
+            ExportColumn::Amount => self.formatted_amount(if let SignConvention::WithdrawalsPositive = self.sign { -amount } else { amount }),
+            ExportColumn::Credit => if amount > D64::ZERO { self.formatted_amount(amount) } else { String::new() },
+            ExportColumn::Withdrawal => if amount < D64::ZERO { self.formatted_amount(amount.abs()) } else { String::new() },
+            ExportColumn::Balance => self.formatted_amount(entry.balance)
+        }
+    }
+
+    fn formatted_amount(&self, amount: D64) -> String {
+        format!("{:.2}", amount).replace('.', &self.decimal_separator.to_string())
+    }
+}
@@ -1,19 +1,112 @@
-use std::fmt;
+use std::{ fmt, io };
+use fastnum::D64;
+use rusqlite::ErrorCode;
 
-pub enum ImportError {
-    TransactionTypeParsingError
+/** Represents anything that can go wrong while working with the register.
+ * Each kind of error has its own exit code,
+ * so that scripts can tell what happened.
+ */
+#[derive(Debug)]
+pub enum CheckbookError {
+    TransactionTypeParsingError,
+    RecordNotFound(String),
+    AccountNotFound(String),
+    AccountClosed(String),
+    AccountExists(String),
+    CategoryNotFound(String),
+    CategoryExists(String),
+    CategoryInUse(String, u32),
+    CategoryHasSubcategories(String),
+    RuleNotFound(u32),
+    ScheduleNotFound(u32),
+    ProfileNotFound(String),
+    Unbalanced(D64),
+    DatabaseUnreadable(String),
+    Schema(String),
+    Migration(i32, String),
+    UnsupportedVersion(i32),
+    Parse(String),
+    InvalidInput(String),
+    ConstraintViolation(String),
+    Database(rusqlite::Error),
+    Io(io::Error)
 }
 
-impl ImportError {
-    pub fn to_string(&self) -> String {
+/// exit code used when an error does not fit any other kind.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// exit code used when the given options cannot be used together, which matches what clap uses.
+pub const EXIT_INVALID_INPUT: i32 = 2;
+
+/// exit code used when an entry, account, category, rule, schedule, or CSV profile cannot be found.
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// exit code used when the database cannot be read, created, or migrated.
+pub const EXIT_DATABASE_UNREADABLE: i32 = 4;
+
+/// exit code used when a file or value cannot be parsed.
+pub const EXIT_PARSE_ERROR: i32 = 5;
+
+/// exit code used when a change would break the rules of the register.
+pub const EXIT_CONSTRAINT_VIOLATION: i32 = 6;
+
+impl CheckbookError {
+    pub fn exit_code(&self) -> i32 {
         match self {
-            Self::TransactionTypeParsingError => "Cannot determine transaction type. Please make sure there is a value in only either the credit or withdrawal column".to_owned()
+            Self::InvalidInput(_) => EXIT_INVALID_INPUT,
+            Self::RecordNotFound(_) | Self::AccountNotFound(_) | Self::CategoryNotFound(_) | Self::RuleNotFound(_) | Self::ScheduleNotFound(_) | Self::ProfileNotFound(_) => EXIT_NOT_FOUND,
+            Self::DatabaseUnreadable(_) | Self::Schema(_) | Self::Migration(_, _) | Self::UnsupportedVersion(_) => EXIT_DATABASE_UNREADABLE,
+            Self::TransactionTypeParsingError | Self::Parse(_) => EXIT_PARSE_ERROR,
+            Self::AccountClosed(_) | Self::AccountExists(_) | Self::CategoryExists(_) | Self::CategoryInUse(_, _) | Self::CategoryHasSubcategories(_) | Self::Unbalanced(_) | Self::ConstraintViolation(_) => EXIT_CONSTRAINT_VIOLATION,
+            Self::Database(_) | Self::Io(_) => EXIT_FAILURE
         }
     }
 }
 
-impl fmt::Display for ImportError {
+impl fmt::Display for CheckbookError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        match self {
+            Self::TransactionTypeParsingError => write!(f, "Cannot determine transaction type. Please make sure there is a value in only either the credit or withdrawal column"),
+            Self::RecordNotFound(id) => write!(f, "entry {} does not exist.", id),
+            Self::AccountNotFound(name) => write!(f, "account {} does not exist.", name),
+            Self::AccountClosed(name) => write!(f, "account {} is closed.", name),
+            Self::AccountExists(name) => write!(f, "account {} already exists.", name),
+            Self::CategoryNotFound(name) => write!(f, "category {} does not exist.", name),
+            Self::CategoryExists(name) => write!(f, "category {} already exists.", name),
+            Self::CategoryInUse(name, count) => write!(f, "category {} is used by {} entries. Please give another category to move them to with --reassign.", name, count),
+            Self::CategoryHasSubcategories(name) => write!(f, "category {} has other categories nested under it. Please merge or delete them first.", name),
+            Self::RuleNotFound(id) => write!(f, "rule {} does not exist.", id),
+            Self::ScheduleNotFound(id) => write!(f, "schedule {} does not exist.", id),
+            Self::ProfileNotFound(name) => write!(f, "CSV profile {} does not exist.", name),
+            Self::Unbalanced(difference) => write!(f, "the cleared balance is off from the statement by {:.2}, so nothing was reconciled.", difference),
+            Self::DatabaseUnreadable(reason) => write!(f, "could not read database: {}", reason),
+            Self::Schema(reason) => write!(f, "could not create database from schema: {}", reason),
+            Self::Migration(version, reason) => write!(f, "could not migrate database to version {}: {}", version, reason),
+            Self::UnsupportedVersion(version) => write!(f, "database version {} is newer than the latest supported version.", version),
+            Self::Parse(reason) => write!(f, "{}", reason),
+            Self::InvalidInput(reason) => write!(f, "{}", reason),
+            Self::ConstraintViolation(reason) => write!(f, "{}", reason),
+            Self::Database(error) => write!(f, "{}", error),
+            Self::Io(error) => write!(f, "{}", error)
+        }
     }
-}
\ No newline at end of file
+}
+
+impl std::error::Error for CheckbookError {}
+
+/// sort database errors into constraint violations, unreadable databases, and everything else.
+impl From<rusqlite::Error> for CheckbookError {
+    fn from(error: rusqlite::Error) -> Self {
+        match error.sqlite_error_code() {
+            Some(ErrorCode::ConstraintViolation) => Self::ConstraintViolation(error.to_string()),
+            Some(ErrorCode::NotADatabase) | Some(ErrorCode::DatabaseCorrupt) | Some(ErrorCode::CannotOpen) | Some(ErrorCode::PermissionDenied) => Self::DatabaseUnreadable(error.to_string()),
+            _ => Self::Database(error)
+        }
+    }
+}
+
+impl From<io::Error> for CheckbookError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
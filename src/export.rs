@@ -1,97 +1,364 @@
-use std::path::Path;
+use std::{ collections::{ HashMap, HashSet }, fs, io, path::Path };
 
 use clap::Parser;
-use crate::{ database::*, shared::* };
-use bcheck::{ Record, Save, TransactionType };
-use qif::{ DateFormat, QIF, Transaction as QIFTransaction, TransactionBuildingError, Section };
+use crate::{ amount::*, database::*, delimited_layout::*, errors::CheckbookError, export_column::ExportColumn, journal::*, ledger_entry::LedgerEntry, ofx::*, records::Records, shared::*, sign_convention::SignConvention, split::Split, status::* };
+use bcheck::{ Record, Save, Transaction, TransactionType };
+use chrono::Local;
+use fastnum::D64;
+use qif::{ DateFormat, QIF, Split as QIFSplit, Transaction as QIFTransaction, TransactionBuildingError, Section };
 use spreadsheet_ods::{ write_ods, OdsError, Sheet, WorkBook };
-use xlsxwriter::prelude::*;
+use rust_xlsxwriter::{ Workbook, Worksheet, XlsxError };
 use icu_locid::locale;
+use rusqlite::Connection;
 
 
+/// the start of the identifier given to the opening balance entry of an export.
+const OPENING_BALANCE_PREFIX: &str = "opening-balance-";
+
+/** determine whether an identifier belongs to the opening balance entry of an export.
+ * That entry stands for entries that are not in the export,
+ * so it is not an entry of its own.
+ */
+pub fn is_opening_balance_id(id: &str) -> bool {
+    id.to_lowercase().starts_with(OPENING_BALANCE_PREFIX)
+}
+
 #[derive(Parser)]
-#[clap(version = "0.5", author = "Bryce Campbell", long_about = "Export ledger to file. \r\n\r\nExports are done based upon given file extension, with support for: \r\n\r\n* JSON (bcheck)\r\n* QIF\r\n* Excel (xlsx)\r\n* ODS\r\n* TSV \r\n\r\nDefault output is TSV. \r\n\r\nExcel and ODS output will include headers and a column for balances, unlike TSV, which includes neither. \r\n\r\nHowever, TSV files can only be imported when they have the tsv extension.")]
+#[clap(version = "0.5", author = "Bryce Campbell", long_about = "Export ledger to file. \r\n\r\nExports are done based upon given file extension, with support for: \r\n\r\n* JSON (bcheck)\r\n* QIF\r\n* OFX\r\n* ledger, hledger, and beancount\r\n* Excel (xlsx)\r\n* ODS\r\n* CSV\r\n* TSV \r\n\r\nDefault output is TSV. \r\n\r\nExcel and ODS output will include headers and a column for balances, unlike TSV, which includes neither. \r\n\r\nHowever, TSV files can only be imported when they have the tsv extension.\r\n\r\nTo export a single account, use --account. Balances will then be for that account alone.\r\n\r\nEntries can be narrowed down with the same filters as the list command, such as -c, -v, -m, -s, and -t, along with --from and --to dates, like this: \r\n\r\nrcheckbook export -o quarter.xlsx --from 2026-07-01 --to 2026-09-30 --opening-balance \r\n\r\nWith --opening-balance, the export starts with an entry for the balance just before the first entry exported, and balances carry on from it, so that they add up for the entries exported. Importing the export leaves that entry out, except from QIF files.\r\n\r\nSplits are included in JSON, QIF, ledger, hledger, and beancount exports.\r\n\r\nCleared entries are marked with a C in TSV and spreadsheet exports, and with c in QIF exports, while reconciled entries are marked with Y and * respectively.\r\n\r\nOFX exports are a single bank statement, where each entry is identified by its own identifier, and the ending balance is the balance of the last entry. The routing number of the bank must be given with --bank-id, and --account-type can be checking, savings, money-market, or credit-line, like this: \r\n\r\nrcheckbook export -o checking.ofx --account Checking --bank-id 021000021\r\n\r\nCSV exports have a header and the same columns as spreadsheet exports. The columns and their order can be chosen for CSV and TSV exports alike, like this: \r\n\r\nrcheckbook export -o register.csv --columns date,vendor,amount,balance --date-format %m/%d/%Y \r\n\r\nGive amount for a single signed amount, or credit and withdrawal for separate columns. The header, delimiter, and decimal separator can be changed as well, and --sign withdrawals-positive shows withdrawals as positive amounts. Fields holding the delimiter, quotes, or line breaks are quoted, as RFC 4180 describes.\r\n\r\nLedger, hledger, and beancount exports are chosen with the ledger, journal, and beancount extensions. Each entry is posted between an Assets account named after its account and an Expenses or Income account named after its category.")]
 pub struct Export {
 
     #[clap(default_value = "~/.checkbook/register.db")]
     pub file_path: String,
 
     #[clap(long, short)]
-    pub output_file: String
+    pub output_file: String,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long, short)]
+    pub category: Option<String>,
+
+    #[clap(long, short)]
+    pub vendor: Option<String>,
+
+    #[clap(long, short)]
+    pub memo: Option<String>,
+
+    #[clap(long, short)]
+    pub reconciled: bool,
+
+    #[clap(long)]
+    pub not_reconciled: bool,
+
+    #[clap(long, short, value_enum, conflicts_with_all = ["reconciled", "not_reconciled"])]
+    pub status: Option<Status>,
+
+    #[clap(long, short)]
+    pub transaction_type: Option<TransactionType>,
+
+    #[clap(long)]
+    pub from: Option<String>,
+
+    #[clap(long)]
+    pub to: Option<String>,
+
+    #[clap(long)]
+    pub opening_balance: bool,
+
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub columns: Vec<ExportColumn>,
+
+    #[clap(long)]
+    pub header: Option<bool>,
+
+    #[clap(long)]
+    pub delimiter: Option<char>,
+
+    #[clap(long)]
+    pub date_format: Option<String>,
+
+    #[clap(long)]
+    pub decimal_separator: Option<char>,
+
+    #[clap(long, value_enum)]
+    pub sign: Option<SignConvention>,
+
+    #[clap(long)]
+    pub bank_id: Option<String>,
+
+    #[clap(long, value_enum)]
+    pub account_type: Option<OfxAccountType>
 }
 
 impl Export {
-    pub fn run(&self) {
-        self.export();
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = open_database(&self.file_path)?;
+        self.export(&db)
     }
 
-    fn export(&self) {
+    fn export(&self, db: &Connection) -> Result<(), CheckbookError> {
         let destination_path = real_path(&self.output_file);
-        let records = load_records_from_db(&self.file_path);
+        let entries = self.selected_entries(db)?;
+        let records: Vec<Record> = entries.iter().map(|entry| entry.record.clone()).collect();
+        let balances: Vec<D64> = entries.iter().map(|entry| entry.balance).collect();
+        let statuses: Vec<Status> = entries.iter().map(|entry| entry.status.clone()).collect();
+        let cleared: Vec<String> = entries.iter().filter(|entry| entry.status == Status::Cleared).map(|entry| entry.record.id.to_lowercase()).collect();
+        let splits = load_splits_from_db(db)?;
+
+        if self.has_layout_options() && !destination_path.ends_with(".csv") && !destination_path.ends_with(".tsv") {
+            return Err(CheckbookError::InvalidInput("--columns, --header, --delimiter, --date-format, --decimal-separator, and --sign can only be used with CSV and TSV exports.".to_string()));
+        }
+
+        if (self.bank_id.is_some() || self.account_type.is_some()) && !destination_path.ends_with(".ofx") {
+            return Err(CheckbookError::InvalidInput("--bank-id and --account-type can only be used with OFX exports.".to_string()));
+        }
 
         match destination_path {
-            p if p.ends_with(".bcheck") => if let Err(error) = records.save(&p) {
-                println!("{}", error);
-            },
-            p if p.ends_with(".qif") => if let Err(error) = store_to_qif(records).save(&p, &DateFormat::MonthDayFullYear) {
-                println!("{}", error);
-            },
-            ref p if p.ends_with(".ods") => if let Err(error) = write_ods(&mut create_ods_book(records, &self.file_path), Path::new(&destination_path)) {
-                match error {
-                    OdsError::Io(error) => println!("{}", error),
-                    OdsError::Utf8(error) => println!("{}", error),
-                    OdsError::Xml(error) => println!("{}", error),
-                    OdsError::Zip(error) => println!("{}", error),
-                    _ => ()
-                }
-            },
-            ref p if p.ends_with(".xlsx") => if let Err(error) = create_xlsx_book(&destination_path, records, &self.file_path) {
-                println!("{}", error)
-            },
-            _ => if let Err(error) = records.save_tsv(&destination_path) {
-                println!("{}", error);
+            p if p.ends_with(".bcheck") => save_bcheck(&records, &splits, &cleared, &p)?,
+            p if p.ends_with(".ofx") => fs::write(&p, statement_to_ofx(records, &balances, &self.ofx_account()?))?,
+            p if JournalFormat::from_path(&p).is_some() => save_journal(db, &entries, &splits, &p)?,
+            p if p.ends_with(".qif") => fs::write(&p, qif_with_cleared_markers(&store_to_qif(records, &splits, &cleared).to_string(&DateFormat::MonthDayFullYear)))?,
+            ref p if p.ends_with(".ods") => write_ods(&mut create_ods_book(records, &balances, &statuses), Path::new(&destination_path)).map_err(|error| match error {
+                OdsError::Io(error) => CheckbookError::Io(error),
+                error => CheckbookError::Io(io::Error::other(error.to_string()))
+            })?,
+            ref p if p.ends_with(".csv") => fs::write(p, self.delimited_layout(&CSV_COLUMNS, ',', true).entries_to_text(&entries)?)?,
+            ref p if p.ends_with(".xlsx") => create_xlsx_book(&destination_path, records, &balances, &statuses).map_err(|error| CheckbookError::Io(io::Error::other(error.to_string())))?,
+            ref p if p.ends_with(".tsv") && self.has_layout_options() => fs::write(p, self.delimited_layout(&TSV_COLUMNS, '\t', false).entries_to_text(&entries)?)?,
+            _ => save_tsv(&records, &statuses, &destination_path)?
+        }
+
+        Ok(())
+    }
+
+    /** retrieve the entries to export, narrowed down by the filters and dates given.
+     * With --opening-balance, the entries are preceded by one for the balance before them,
+     * and balances carry on from there, so that they add up for the entries exported.
+     */
+    fn selected_entries(&self, db: &Connection) -> Result<Vec<LedgerEntry>, CheckbookError> {
+        if self.reconciled && self.not_reconciled {
+            return Err(CheckbookError::InvalidInput("Please use only one flag!\r\nNo transactions can be both reconciled and unreconciled.".to_string()));
+        }
+
+        let from = self.from.as_deref().map(parse_date).transpose()?;
+        let to = self.to.as_deref().map(parse_date).transpose()?;
+
+        if let (Some(from), Some(to)) = (from, to) && from > to {
+            return Err(CheckbookError::InvalidInput("The from date must not be after the to date.".to_string()));
+        }
+
+        let entries = load_ledger_from_db(db, &self.account)?;
+        let store = Records::from(entries.clone());
+        let statuses = statuses_to_show(&self.status, self.reconciled, self.not_reconciled);
+
+        let selected_ids: HashSet<String> = store.filtered_records(&load_splits_from_db(db)?, &self.category, &self.vendor, &self.memo, &statuses, &self.transaction_type).into_iter()
+        .filter(|record| from.is_none_or(|from| record.transaction.date >= from) && to.is_none_or(|to| record.transaction.date <= to))
+        .map(|record| record.id.to_lowercase())
+        .collect();
+
+        let selected_entries: Vec<LedgerEntry> = entries.iter().filter(|entry| selected_ids.contains(&entry.record.id.to_lowercase())).cloned().collect();
+
+        if !self.opening_balance {
+            return Ok(selected_entries);
+        }
+
+        let Some(opening_date) = from.or(selected_entries.first().map(|entry| entry.record.transaction.date)) else {
+            return Ok(selected_entries);
+        };
+
+        // entries are in ledger order, so the balance carries over from whatever comes just before the first entry exported, even on the same day.
+        let preceding_entry = match selected_entries.first() {
+            Some(first_entry) => entries.iter().take_while(|entry| entry.record.id != first_entry.record.id).last(),
+            None => entries.iter().rev().find(|entry| entry.record.transaction.date < opening_date)
+        };
+
+        let mut balance = preceding_entry.map(|entry| entry.balance).unwrap_or(D64::ZERO);
+
+        let mut opening_transaction = Transaction::builder()
+        .set_date(&opening_date.format("%Y-%m-%d").to_string())
+        .set_vendor("Opening Balance")
+        .set_category("Opening Balance")
+        .build();
+
+        set_signed_amount(&mut opening_transaction, balance);
+
+        // the identifier marks the entry as an opening balance, so that importing the export leaves it out.
+        let opening_id = match &self.account {
+            Some(account) => format!("{}{}-{}", OPENING_BALANCE_PREFIX, account.to_lowercase(), opening_date.format("%Y-%m-%d")),
+            None => format!("{}{}", OPENING_BALANCE_PREFIX, opening_date.format("%Y-%m-%d"))
+        };
+
+        let mut balanced_entries = vec![LedgerEntry::from(Record::from(&opening_id, opening_transaction), balance, Status::Uncleared)];
+
+        for entry in selected_entries {
+            balance += signed_amount(&entry.record.transaction);
+            balanced_entries.push(LedgerEntry::from(entry.record, balance, entry.status));
+        }
+
+        Ok(balanced_entries)
+    }
+
+    /** retrieve the layout of a CSV or TSV export from the options given.
+     * Options that are not given fall back to those of the format,
+     * where CSV files have a header and a balance column, while TSV files have neither.
+     */
+    fn delimited_layout(&self, columns: &[ExportColumn], delimiter: char, has_header: bool) -> DelimitedLayout {
+        DelimitedLayout {
+            columns: if self.columns.is_empty() { columns.to_vec() } else { self.columns.clone() },
+            has_header: self.header.unwrap_or(has_header),
+            delimiter: self.delimiter.unwrap_or(delimiter),
+            date_format: self.date_format.clone().unwrap_or("%Y-%m-%d".to_string()),
+            decimal_separator: self.decimal_separator.unwrap_or('.'),
+            sign: self.sign.clone().unwrap_or(SignConvention::DepositsPositive)
+        }
+    }
+
+    /** retrieve the account an OFX export is for.
+     * The bank has to be given, since finance tools reject statements without a valid routing number,
+     * while the account is named after the one exported.
+     */
+    fn ofx_account(&self) -> Result<OfxAccount, CheckbookError> {
+        let Some(bank_id) = self.bank_id.clone().filter(|bank_id| !bank_id.trim().is_empty()) else {
+            return Err(CheckbookError::InvalidInput("Please give the routing number of the bank with --bank-id, which OFX statements must include.".to_string()));
+        };
+
+        Ok(OfxAccount {
+            bank_id: bank_id.trim().to_string(),
+            account_id: self.account.clone().unwrap_or("Default".to_string()),
+            account_type: self.account_type.clone().unwrap_or(OfxAccountType::Checking)
+        })
+    }
+
+    /// check if any option changing the layout of a TSV export was given, since TSV exports are otherwise written the way bcheck reads them.
+    fn has_layout_options(&self) -> bool {
+        !self.columns.is_empty() || self.header.is_some() || self.delimiter.is_some() || self.date_format.is_some() || self.decimal_separator.is_some() || self.sign.is_some()
+    }
+}
+
+/** save records to a bcheck file.
+ * Splits are stored in each transaction under a splits key, 
+ * and cleared entries are marked with a cleared key,
+ * both of which are ignored by anything that does not know about them.
+ */
+fn save_bcheck(records: &Vec<Record>, splits: &HashMap<String, Vec<Split>>, cleared: &[String], p: &str) -> Result<(), io::Error> {
+    if splits.is_empty() && cleared.is_empty() {
+        return records.save(p);
+    }
+
+    let mut json = serde_json::to_value(records)?;
+
+    if let serde_json::Value::Array(entries) = &mut json {
+        for (entry, record) in entries.iter_mut().zip(records) {
+            if let Some(record_splits) = splits.get(&record.id.to_lowercase()) {
+                entry["transaction"]["splits"] = serde_json::to_value(record_splits)?;
+            }
+
+            if cleared.contains(&record.id.to_lowercase()) {
+                entry["transaction"]["cleared"] = serde_json::Value::Bool(true);
             }
         }
     }
+
+    fs::write(p, serde_json::to_string_pretty(&json)?)
+}
+
+/// save records to a TSV file, with the status of each record in place of whether it is reconciled.
+fn save_tsv(records: &[Record], statuses: &[Status], p: &str) -> Result<(), io::Error> {
+    let tsv: String = records.iter().zip(statuses).map(|(record, status)| record_with_status(record, status) + "\r\n").collect();
+
+    fs::write(p, tsv)
+}
+
+/// present records as an OFX statement, whose ending balance is the balance of the last record.
+fn statement_to_ofx(records: Vec<Record>, balances: &[D64], account: &OfxAccount) -> String {
+    let ledger_balance = LedgerBalance {
+        amount: balances.last().copied().unwrap_or(D64::ZERO),
+        date: records.last().map(|record| record.transaction.date).unwrap_or(Local::now().date_naive())
+    };
+
+    let statement = OfxStatement {
+        transactions: records.into_iter().map(|record| OfxTransaction { fitid: record.id.clone(), record }).collect(),
+        ledger_balance: Some(ledger_balance)
+    };
+
+    statement.to_ofx(account)
+}
+
+/** save entries to a ledger, hledger, or beancount journal, depending on the extension.
+ * Transfers without a category are posted against a transfers account,
+ * since the other half is exported as its own transaction.
+ */
+fn save_journal(db: &Connection, entries: &[LedgerEntry], splits: &HashMap<String, Vec<Split>>, p: &str) -> Result<(), CheckbookError> {
+    let Some(format) = JournalFormat::from_path(p) else {
+        return Err(CheckbookError::InvalidInput(format!("{} is not a ledger, hledger, or beancount file.", p)));
+    };
+
+    let accounts = load_record_accounts_from_db(db)?;
+    let transfer_ids = load_transfer_ids_from_db(db)?;
+
+    let journal_entries: Vec<JournalEntry> = entries.iter().map(|entry| {
+        let id = entry.record.id.to_lowercase();
+
+        JournalEntry {
+            record: entry.record.clone(),
+            status: entry.status.clone(),
+            splits: splits.get(&id).cloned().unwrap_or_default(),
+            account: accounts.get(&id).cloned().unwrap_or("Default".to_string()),
+            is_transfer: transfer_ids.contains(&id)
+        }
+    }).collect();
+
+    fs::write(p, entries_to_journal(&journal_entries, format))?;
+
+    Ok(())
 }
 
-fn record_to_qif(record: &Record) -> Result<QIFTransaction, TransactionBuildingError> {
+/** mark cleared transactions in QIF output with c.
+ * The qif crate can only write cleared transactions as X, which usually means reconciled.
+ */
+fn qif_with_cleared_markers(qif: &str) -> String {
+    qif.split("\r\n").map(|line| if line == "CX" { "Cc" } else { line }).collect::<Vec<&str>>().join("\r\n")
+}
+
+fn record_to_qif(record: &Record, splits: &Vec<Split>, is_cleared: bool) -> Result<QIFTransaction, TransactionBuildingError> {
     let format = DateFormat::MonthDayFullYear;
+    let mut builder = QIFTransaction::builder();
+
+    for split in splits {
+        builder.add_split(QIFSplit::from(split.category.clone(), split.memo.clone(), split.amount.to_f64()));
+    }
     
-    QIFTransaction::builder()
+    builder
     .set_date(&record.transaction.date.format(format.chrono_str()).to_string(), &format)
     .set_check_number(record.transaction.check_number.unwrap_or(0))
     .set_vendor(&record.transaction.vendor)
-    .set_amount(if let TransactionType::Deposit = record.transaction.transaction_type {
-        record.transaction.amount.to_f64()
-    } else {
-        record.transaction.amount.to_f64()*-1.0
-    })
+    .set_amount(signed_amount(&record.transaction).to_f64())
     .set_category(&record.transaction.category.clone().unwrap_or("".to_owned()))
     .set_memo(&record.transaction.memo)
     .set_status(if record.transaction.is_reconciled { 
         "*" 
+    } else if is_cleared {
+        "X"
     } else {
         ""
     })
     .build()
 }
 
-fn records_to_qif_transactions(records: Vec<Record>) -> Vec<QIFTransaction> {
-    records.into_iter().map(|r| record_to_qif(&r))
-    .filter(|t| t.is_ok())
-    .map(|r| r.unwrap())
+fn records_to_qif_transactions(records: Vec<Record>, splits: &HashMap<String, Vec<Split>>, cleared: &[String]) -> Vec<QIFTransaction> {
+    records.into_iter().flat_map(|r| record_to_qif(&r, splits.get(&r.id.to_lowercase()).unwrap_or(&vec![]), cleared.contains(&r.id.to_lowercase())))
     .collect()
 }
 
-fn store_to_qif(records: Vec<Record>) -> QIF {
+fn store_to_qif(records: Vec<Record>, splits: &HashMap<String, Vec<Split>>, cleared: &[String]) -> QIF {
     let mut qif = QIF::builder();
 
     let mut bank_builder = Section::builder();
     
     bank_builder.set_type("Bank");
 
-    for transaction in records_to_qif_transactions(records.clone()) {
+    for transaction in records_to_qif_transactions(records.clone(), splits, cleared) {
         bank_builder.add_transaction(transaction);
     }
 
@@ -102,14 +369,14 @@ fn store_to_qif(records: Vec<Record>) -> QIF {
     qif.build()
 }
 
-fn create_ods_book(records: Vec<Record>, db: &str) -> WorkBook {
+fn create_ods_book(records: Vec<Record>, balances: &Vec<D64>, statuses: &[Status]) -> WorkBook {
     let mut workbook = WorkBook::new(locale!("en_US"));
 
     let mut sheet = Sheet::new("Register");
 
     sheet.set_value(0, 1, "Date");
     sheet.set_value(0, 2, "Check #");
-    sheet.set_value(0, 3, "Reconciled");
+    sheet.set_value(0, 3, "Status");
     sheet.set_value(0, 4, "Category");
     sheet.set_value(0, 5, "Vendor");
     sheet.set_value(0, 6, "Memo");
@@ -117,10 +384,10 @@ fn create_ods_book(records: Vec<Record>, db: &str) -> WorkBook {
     sheet.set_value(0, 8, "Withdrawal");
     sheet.set_value(0, 9, "Balance");
 
-    for (index, record) in records.iter().enumerate() {
+    for (index, ((record, balance), status)) in records.iter().zip(balances).zip(statuses).enumerate() {
         let row_index = index+1;
 
-        add_record_to_ods_sheet(record, row_index.try_into().expect("Could not cast number"), db, &mut sheet)
+        add_record_to_ods_sheet(record, row_index.try_into().expect("Could not cast number"), balance, status, &mut sheet)
     }
 
     workbook.push_sheet(sheet);
@@ -128,7 +395,7 @@ fn create_ods_book(records: Vec<Record>, db: &str) -> WorkBook {
     workbook
 }
 
-fn add_record_to_ods_sheet(record: &Record, row_index: u32, db: &str, sheet: &mut Sheet) {
+fn add_record_to_ods_sheet(record: &Record, row_index: u32, balance: &D64, status: &Status, sheet: &mut Sheet) {
     sheet.set_value(row_index, 0, record.id.clone());
     
     let date_string = format!("{}", record.transaction.date.format("%Y-%m-%d"));
@@ -140,11 +407,7 @@ fn add_record_to_ods_sheet(record: &Record, row_index: u32, db: &str, sheet: &mu
         String::default()
     });
 
-    sheet.set_value(row_index, 3, if record.transaction.is_reconciled {
-        "Y"
-    } else {
-        "N"
-    });
+    sheet.set_value(row_index, 3, status.letter());
 
     sheet.set_value(row_index, 4, if let Some(category) = &record.transaction.category {
         category.to_owned()
@@ -163,41 +426,40 @@ fn add_record_to_ods_sheet(record: &Record, row_index: u32, db: &str, sheet: &mu
         sheet.set_value(row_index, 8, amount_string)
     };
 
-    sheet.set_value(row_index, 9, retrieve_balance_for_record(db, record.clone()));
+    sheet.set_value(row_index, 9, balance.to_f64());
 }
 
-fn create_xlsx_book(p: &str, records: Vec<Record>, db: &str) -> Result<(), XlsxError> {
-    let workbook = Workbook::new(p)?;
+fn create_xlsx_book(p: &str, records: Vec<Record>, balances: &[D64], statuses: &[Status]) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
 
-    let mut sheet = workbook.add_worksheet(None)?;
+    let sheet = workbook.add_worksheet();
 
-    sheet.write_blank(0, 0, None)?;
-    sheet.write_string(0, 1, "Date", None)?;
-    sheet.write_string(0, 2, "Check #", None)?;
-    sheet.write_string(0, 3, "Reconciled", None)?;
-    sheet.write_string(0, 4, "Category", None)?;
-    sheet.write_string(0, 5, "Vendor", None)?;
-    sheet.write_string(0, 6, "Memo", None)?;
-    sheet.write_string(0, 7, "Credit", None)?;
-    sheet.write_string(0, 8, "Withdrawal", None)?;
-    sheet.write_string(0, 9, "Balance", None)?;
+    sheet.write_string(0, 1, "Date")?;
+    sheet.write_string(0, 2, "Check #")?;
+    sheet.write_string(0, 3, "Status")?;
+    sheet.write_string(0, 4, "Category")?;
+    sheet.write_string(0, 5, "Vendor")?;
+    sheet.write_string(0, 6, "Memo")?;
+    sheet.write_string(0, 7, "Credit")?;
+    sheet.write_string(0, 8, "Withdrawal")?;
+    sheet.write_string(0, 9, "Balance")?;
 
-    for (index, record) in records.iter().enumerate() {
+    for (index, ((record, balance), status)) in records.iter().zip(balances).zip(statuses).enumerate() {
         let row_index = index+1;
 
-        add_record_to_xlsx_sheet(record, row_index.try_into().expect("Could not cast number"), db, &mut sheet)?;
+        add_record_to_xlsx_sheet(record, row_index.try_into().expect("Could not cast number"), balance, status, sheet)?;
     }
 
-    workbook.close()?;
+    workbook.save(p)?;
 
     Ok(())
 }
 
-fn add_record_to_xlsx_sheet(record: &Record, row_index: u32, db: &str, sheet: &mut Worksheet) -> Result<(), XlsxError> {
-    sheet.write_string(row_index, 0, &record.id, None)?;
+fn add_record_to_xlsx_sheet(record: &Record, row_index: u32, balance: &D64, status: &Status, sheet: &mut Worksheet) -> Result<(), XlsxError> {
+    sheet.write_string(row_index, 0, &record.id)?;
 
     let date_string = format!("{}", record.transaction.date.format("%Y-%m-%d"));
-    sheet.write_string(row_index, 1, &date_string, None)?;
+    sheet.write_string(row_index, 1, &date_string)?;
 
     let check_number = if let Some(check_number) = record.transaction.check_number {
         format!("{}", check_number)
@@ -205,13 +467,9 @@ fn add_record_to_xlsx_sheet(record: &Record, row_index: u32, db: &str, sheet: &m
         String::default()
     };
 
-    sheet.write_string(row_index, 2, &check_number, None)?;
+    sheet.write_string(row_index, 2, &check_number)?;
 
-    sheet.write_string(row_index, 3, if record.transaction.is_reconciled {
-        "Y"
-    } else {
-        "N"
-    }, None)?;
+    sheet.write_string(row_index, 3, status.letter())?;
 
     let category = if let Some(category) = &record.transaction.category {
         category.to_owned()
@@ -219,20 +477,77 @@ fn add_record_to_xlsx_sheet(record: &Record, row_index: u32, db: &str, sheet: &m
         String::default()
     };
 
-    sheet.write_string(row_index, 4, &category, None)?;
+    sheet.write_string(row_index, 4, &category)?;
 
-    sheet.write_string(row_index, 5, &record.transaction.vendor, None)?;
-    sheet.write_string(row_index, 6, &record.transaction.memo, None)?;
+    sheet.write_string(row_index, 5, &record.transaction.vendor)?;
+    sheet.write_string(row_index, 6, &record.transaction.memo)?;
 
     if let TransactionType::Deposit = record.transaction.transaction_type {
-        sheet.write_number(row_index, 7, record.transaction.amount.to_f64(), None)?;
-        sheet.write_blank(row_index, 8, None)?;
+        sheet.write_number(row_index, 7, record.transaction.amount.to_f64())?;
     } else {
-        sheet.write_blank(row_index, 7, None)?;
-        sheet.write_number(row_index, 8, record.transaction.amount.to_f64(), None)?;
+        sheet.write_number(row_index, 8, record.transaction.amount.to_f64())?;
     }
 
-    sheet.write_number(row_index, 9, retrieve_balance_for_record(db, record.clone()), None)?;
+    sheet.write_number(row_index, 9, balance.to_f64())?;
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::Import;
+    use std::env;
+
+    /// create a register with an entry on the first of October and two on the second, the last of which is groceries.
+    fn register(name: &str) -> (std::path::PathBuf, String) {
+        let directory = env::temp_dir().join(format!("rcheckbook-{}-{}", name, std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8").to_string();
+
+        let db = copy_database_if_not_exists(&p).expect("could not create database");
+
+        for (id, date, category, amount) in [("A", "2026-10-01", "Groceries", "100.00"), ("B", "2026-10-02", "Dining", "-10.00"), ("C", "2026-10-02", "Groceries", "-5.00")] {
+            let mut transaction = Transaction::builder().set_date(date).set_vendor("Vendor").set_category(category).build();
+            set_signed_amount(&mut transaction, amount.parse::<D64>().expect("could not parse amount"));
+
+            add_record_to_db(&db, &Record::from(id, transaction), &None).expect("could not add entry");
+        }
+
+        (directory, p)
+    }
+
+    #[test]
+    fn opening_balance_includes_earlier_entries_on_the_same_day() {
+        let (directory, p) = register("opening-balance");
+        let export = Export::try_parse_from(["export", &p, "-o", "groceries.tsv", "-c", "Groceries", "--from", "2026-10-02", "--opening-balance"]).expect("could not parse arguments");
+
+        let db = open_database(&p).expect("could not open database");
+        let entries = export.selected_entries(&db).expect("could not select entries");
+        let balances: Vec<String> = entries.iter().map(|entry| format!("{:.2}", entry.balance)).collect();
+
+        assert!(is_opening_balance_id(&entries[0].record.id));
+        assert_eq!(balances, vec!["90.00", "85.00"]);
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+
+    #[test]
+    fn skips_opening_balance_when_imported() {
+        let (directory, p) = register("opening-balance-import");
+        let output = directory.join("october.tsv");
+        let o = output.to_str().expect("temporary directory is not valid UTF-8");
+
+        Export::try_parse_from(["export", &p, "-o", o, "--from", "2026-10-02", "--opening-balance"]).expect("could not parse arguments").run().expect("could not export register");
+        Import::try_parse_from(["import", &p, "-i", o]).expect("could not parse arguments").run().expect("could not import export");
+
+        let db = open_database(&p).expect("could not open database");
+        let ledger = load_ledger_from_db(&db, &None).expect("could not load ledger");
+
+        assert_eq!(ledger.len(), 3);
+        assert_eq!(ledger.last().map(|entry| format!("{:.2}", entry.balance)).as_deref(), Some("85.00"));
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+}
@@ -0,0 +1,42 @@
+use clap::ValueEnum;
+use crate::shared::display_value_name;
+
+/** Represents a column that can be included in a CSV or TSV export.
+ * Amounts are either a single signed amount,
+ * or separate credit and withdrawal columns like the spreadsheet exports have.
+ */
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum ExportColumn {
+    Id,
+    Date,
+    Check,
+    Status,
+    Category,
+    Vendor,
+    Memo,
+    Amount,
+    Credit,
+    Withdrawal,
+    Balance
+}
+
+impl ExportColumn {
+    /// retrieve the heading of the column, which matches those of the spreadsheet exports.
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Self::Id => "ID",
+            Self::Date => "Date",
+            Self::Check => "Check #",
+            Self::Status => "Status",
+            Self::Category => "Category",
+            Self::Vendor => "Vendor",
+            Self::Memo => "Memo",
+            Self::Amount => "Amount",
+            Self::Credit => "Credit",
+            Self::Withdrawal => "Withdrawal",
+            Self::Balance => "Balance"
+        }
+    }
+}
+
+display_value_name!(ExportColumn);
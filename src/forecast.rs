@@ -0,0 +1,170 @@
+use bcheck::Record;
+use chrono::{ Duration, Local, Months, NaiveDate };
+use clap::Parser;
+use crate::amount::signed_amount;
+use crate::category_path::is_within;
+use crate::date_range::DateRange;
+use crate::database::*;
+use crate::errors::CheckbookError;
+use crate::forecast_source::ForecastSource;
+use crate::records::Records;
+use crate::scheduled_entry::ScheduledEntry;
+use crate::split::Split;
+use fastnum::D64;
+use std::collections::HashMap;
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Forecast the balance of the ledger. \r\n\r\nTo see how the balance will change over the next 30 days, do this: \r\n\r\nrcheckbook forecast \r\n\r\nThe balance is projected day by day from today, using scheduled entries that have not been posted yet, along with the average amount spent and earned each day in each category over the last few months. The daily average is the total of the categories, each of which is shown beneath it. Use -s to only use one or the other, and --history-months to change how far back averages go. \r\n\r\nEntries already in the register that are dated in the future are always included. \r\n\r\nThe lowest projected balance is shown at the end, and any day where the balance drops below the threshold is flagged. The threshold is zero, unless another is given with --threshold. \r\n\r\nForecasts cover every account combined, unless a single account is given with --account.")]
+pub struct Forecast {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    pub days: u32,
+
+    #[clap(long, short, value_enum, default_value_t = ForecastSource::Both)]
+    pub source: ForecastSource,
+
+    #[clap(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    pub history_months: u32,
+
+    #[clap(long, allow_hyphen_values = true, default_value = "0.0")]
+    pub threshold: D64,
+
+    #[clap(long)]
+    pub account: Option<String>
+}
+
+impl Forecast {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        let record_store = Records::from(load_ledger_from_db(&db, &self.account)?);
+        let transfers = load_transfer_ids_from_db(&db)?;
+        let splits = load_splits_from_db(&db)?;
+        let today = Local::now().date_naive();
+        let last_day = today + Duration::days(i64::from(self.days));
+
+        let schedules: Vec<ScheduledEntry> = if self.source == ForecastSource::History {
+            vec![]
+        } else {
+            load_schedules_from_db(&db)?.into_iter().filter(|schedule| self.account.as_ref().is_none_or(|account| schedule.account.as_ref().is_some_and(|name| name.to_lowercase() == account.to_lowercase()))).collect()
+        };
+
+        let records = record_store.sorted_records();
+
+        let opening_balance = records.iter().rfind(|record| record.transaction.date <= today).map(|record| record_store.balance_for_record(record)).unwrap_or(D64::ZERO);
+
+        let mut changes: HashMap<NaiveDate, D64> = HashMap::new();
+
+        for record in records.iter().filter(|record| record.transaction.date > today && record.transaction.date <= last_day) {
+            *changes.entry(record.transaction.date).or_insert(D64::ZERO) += signed_amount(&record.transaction);
+        }
+
+        for schedule in &schedules {
+            for date in schedule.forecast_dates(today, last_day) {
+                *changes.entry(date).or_insert(D64::ZERO) += schedule.amount;
+            }
+        }
+
+        let category_averages = if self.source == ForecastSource::Scheduled {
+            vec![]
+        } else {
+            let history = DateRange::from(today - Months::new(self.history_months), today);
+            Self::category_averages(&records, &transfers, &splits, &schedules, history)
+        };
+
+        println!("{}", self.create_string(opening_balance, &changes, &category_averages, today));
+
+        Ok(())
+    }
+
+    /** retrieve the average amount spent and earned each day in each category over the given range, sorted by category.
+     * Entries in a category or from a vendor that has a schedule are left out,
+     * so that they are not counted twice, while the rest of the categories still count.
+     * Splits count towards their own categories.
+     */
+    fn category_averages(records: &[Record], transfers: &[String], splits: &HashMap<String, Vec<Split>>, schedules: &[ScheduledEntry], history: DateRange) -> Vec<(String, D64)> {
+        let is_scheduled = |category: &Option<String>, vendor: &str| schedules.iter().any(|schedule| {
+            schedule.vendor.to_lowercase() == vendor.to_lowercase() || category.as_ref().is_some_and(|category| schedule.category.as_ref().is_some_and(|scheduled_category| is_within(category, scheduled_category)))
+        });
+
+        let is_opening_balance = |category: &Option<String>| category.as_ref().is_some_and(|category| category.to_lowercase() == "opening balance");
+
+        let history_records: Vec<&Record> = records.iter().filter(|record| history.contains(record.transaction.date) && !transfers.contains(&record.id.to_lowercase())).collect();
+
+        let mut totals: HashMap<String, D64> = HashMap::new();
+        let mut add_to_category = |category: &Option<String>, amount: D64| *totals.entry(category.clone().unwrap_or("Uncategorized".to_string())).or_insert(D64::ZERO) += amount;
+
+        for record in history_records {
+            if let Some(record_splits) = splits.get(&record.id.to_lowercase()) {
+                for split in record_splits.iter().filter(|split| !is_scheduled(&split.category, &record.transaction.vendor)) {
+                    add_to_category(&split.category, split.amount);
+                }
+            } else if !is_opening_balance(&record.transaction.category) && !is_scheduled(&record.transaction.category, &record.transaction.vendor) {
+                add_to_category(&record.transaction.category, signed_amount(&record.transaction));
+            }
+        }
+
+        let days = D64::from(history.count() as u64);
+        let mut averages: Vec<(String, D64)> = totals.into_iter().filter(|(_, total)| *total != D64::ZERO).map(|(category, total)| (category, total / days)).collect();
+        averages.sort_by_key(|(category, _)| category.to_lowercase());
+
+        averages
+    }
+
+    fn create_string(&self, opening_balance: D64, changes: &HashMap<NaiveDate, D64>, category_averages: &[(String, D64)], today: NaiveDate) -> String {
+        let mut report = String::from("Forecast\r\n\r\n");
+
+        if let Some(account) = &self.account {
+            let account_entry = format!("Account\t{}\r\n\r\n", account);
+            report.push_str(&account_entry);
+        }
+
+        let opening = format!("Opening Balance\t{:.2}\r\n", opening_balance);
+        report.push_str(&opening);
+
+        let daily_average = category_averages.iter().fold(D64::ZERO, |sum, (_, average)| sum + *average);
+
+        if self.source != ForecastSource::Scheduled {
+            let average_entry = format!("Daily Average\t{:.2}\r\n", daily_average);
+            report.push_str(&average_entry);
+
+            for (category, average) in category_averages {
+                let category_entry = format!("\t{}\t{:.2}\r\n", category, average);
+                report.push_str(&category_entry);
+            }
+        }
+
+        report.push_str("\r\n");
+
+        let mut balance = opening_balance;
+        let mut lowest = (opening_balance, today);
+        let mut days_below = 0;
+
+        for date in DateRange::from(today + Duration::days(1), today + Duration::days(i64::from(self.days))) {
+            let change = changes.get(&date).copied().unwrap_or(D64::ZERO) + daily_average;
+            balance += change;
+
+            if balance < lowest.0 {
+                lowest = (balance, date);
+            }
+
+            let entry = if balance < self.threshold {
+                days_below += 1;
+                format!("{}\t{:.2}\t{:.2}\tbelow {:.2}\r\n", date.format("%Y-%m-%d"), change, balance, self.threshold)
+            } else {
+                format!("{}\t{:.2}\t{:.2}\r\n", date.format("%Y-%m-%d"), change, balance)
+            };
+
+            report.push_str(&entry);
+        }
+
+        let lowest_entry = format!("\r\nLowest Balance\t{:.2}\t{}\r\n", lowest.0, lowest.1.format("%Y-%m-%d"));
+        report.push_str(&lowest_entry);
+
+        let below_entry = format!("Days Below {:.2}\t{}", self.threshold, days_below);
+        report.push_str(&below_entry);
+
+        report
+    }
+}
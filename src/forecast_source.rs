@@ -0,0 +1,9 @@
+use clap::ValueEnum;
+
+/// Represents where a forecast gets its upcoming entries from.
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum ForecastSource {
+    Both,
+    Scheduled,
+    History
+}
\ No newline at end of file
@@ -1,129 +1,609 @@
 use clap::Parser;
-use crate::{ database::*, shared::*, errors::ImportError };
+use crate::{ amount::*, category_path::normalized_path, conflict_policy::ConflictPolicy, csv_profile::*, database::*, shared::*, errors::CheckbookError, export::is_opening_balance_id, journal::*, ledger_entry::LedgerEntry, ofx::{ LedgerBalance, OfxStatement }, record_comparison::RecordComparison, rule::apply_rules, sign_convention::SignConvention, split::Split, status::{ record_with_status, Status } };
+use std::{ collections::{ HashMap, HashSet, VecDeque }, fs };
+use fastnum::D64;
+use chrono::NaiveDate;
 use bcheck::{ Record, Transaction };
 use qif::{ DateFormat, QIF, Transaction as QIFTransaction, TransactionStatus, Type as QIFType };
+use rusqlite::Connection;
 use calamine::{ Data, open_workbook, Ods, Xlsx, Reader };
 
 
 #[derive(Parser)]
-#[clap(version = "0.5.1", author = "Bryce Campbell", long_about = "import data from file. \r\n\r\nImport is run based on file extension. Supported formats are: \r\n\r\n* JSON (bcheck)\r\n* TSV\r\n* QIF\r\n* Excel (xlsx)\r\n* ODS")]
+#[clap(version = "0.5.1", author = "Bryce Campbell", long_about = "import data from file. \r\n\r\nImport is run based on file extension. Supported formats are: \r\n\r\n* JSON (bcheck)\r\n* TSV\r\n* CSV\r\n* QIF\r\n* OFX and QFX\r\n* ledger, hledger, and beancount\r\n* Excel (xlsx)\r\n* ODS\r\n\r\nNew entries are placed in the Default account, unless another account is given with --account.\r\n\r\nSplits are imported from QIF, bcheck, ledger, hledger, and beancount files.\r\n\r\nRules added with the rules command are applied to each entry as it is imported.\r\n\r\nEntries marked with a C in TSV and spreadsheet files, or with c in QIF files, are imported as cleared. QIF files mark reconciled entries with * or X.\r\n\r\nSince every bank lays out CSV files differently, the columns have to be given, counting from 1, like this: \r\n\r\nrcheckbook import -i statement.csv --columns date=1,payee=3,amount=5 --date-format %m/%d/%Y --skip-rows 1 \r\n\r\nColumns can be given for date, payee, memo, check, category, and either amount or separate debit and credit columns. Use --sign withdrawals-positive for files that show withdrawals as positive amounts, as many credit cards do, and --decimal-separator , for files that write amounts like 1.234,56. \r\n\r\nA layout can be saved with --save-profile and used again with --profile, where any option given alongside overrides the saved one. \r\n\r\nEntries from OFX and QFX files are imported as cleared, and each one is remembered by the identifier the bank gave it, so importing the same file twice adds nothing new. Entries from CSV and QIF files are matched with those that have the same date, amount, and vendor in the same way. The ending balance of the statement is shown afterwards, ready to reconcile against.\r\n\r\nledger, hledger, and beancount files are read by their ledger, journal, and beancount extensions. The first Assets or Liabilities posting of each transaction is the entry, and the rest give its category or splits. Entries with id metadata, such as those the export command writes, are updated in place when imported again. Transfers posted through Equity:Transfers are linked up again, which needs both accounts in the register.\r\n\r\nEntries already in the register are overwritten by default. Use --dry-run to see which entries are new, identical, or changed, and which rows cannot be read, without importing anything, and --on-conflict skip or fail to keep changed entries from being overwritten.")]
 pub struct Import {
 
     #[clap(default_value = "~/.checkbook/register.db")]
     pub file_path: String,
 
     #[clap(long, short)]
-    pub input_file: String
+    pub input_file: String,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long)]
+    pub profile: Option<String>,
+
+    #[clap(long)]
+    pub save_profile: Option<String>,
+
+    #[clap(long)]
+    pub columns: Option<String>,
+
+    #[clap(long)]
+    pub date_format: Option<String>,
+
+    #[clap(long)]
+    pub delimiter: Option<char>,
+
+    #[clap(long)]
+    pub skip_rows: Option<u32>,
+
+    #[clap(long, value_enum)]
+    pub sign: Option<SignConvention>,
+
+    #[clap(long)]
+    pub decimal_separator: Option<char>,
+
+    #[clap(long)]
+    pub dry_run: bool,
+
+    #[clap(long, value_enum, default_value = "overwrite")]
+    pub on_conflict: ConflictPolicy
 }
 
 impl Import {
-    pub fn run(&self) {
-        copy_database_if_not_exists(&self.file_path);
-        self.import();
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        // a dry run only looks at the register, so it is never created or migrated.
+        let mut db = if self.dry_run { open_database_read_only(&self.file_path)? } else { copy_database_if_not_exists(&self.file_path)? };
+        let transaction = db.transaction()?;
+
+        self.import(&transaction)?;
+
+        if !self.dry_run {
+            transaction.commit()?;
+        }
+
+        Ok(())
     }
 
-    fn import(&self) {
+    fn import(&self, db: &Connection) -> Result<(), CheckbookError> {
         let source_path = real_path(&self.input_file);
-        let records = match source_path {
+        // splits are keyed by lowercase identifier, like those loaded from the database.
+        let mut splits: HashMap<String, Vec<Split>> = HashMap::new();
+        let mut cleared: Vec<String> = vec![];
+        let mut fitids: HashMap<String, String> = HashMap::new();
+        let mut ledger_balances: Vec<LedgerBalance> = vec![];
+        let mut record_accounts: HashMap<String, String> = HashMap::new();
+        let mut invalid_rows: Vec<CheckbookError> = vec![];
+        let mut transfer_pairs: Vec<(String, String)> = vec![];
+        let mut has_identifiers = true;
+
+        let mut records = match source_path {
             ref p if p.ends_with(".bcheck") => {
-                if let Ok(retrieved_records) = Record::from_file(&source_path) {
-                    retrieved_records
-                } else {
-                    vec![]
+                let retrieved_records = Record::from_file(&source_path).map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", self.input_file, error)))?;
+                splits = splits_from_bcheck(p, &retrieved_records);
+                cleared = cleared_from_bcheck(p, &retrieved_records);
+                retrieved_records
+            },
+            ref p if p.ends_with(".qif") => {
+                let content = fs::read_to_string(p).map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", self.input_file, error)))?;
+                let qif = QIF::from_str(&normalized_qif_statuses(&content), &DateFormat::MonthDayFullYear);
+                let (retrieved_records, retrieved_splits, retrieved_cleared) = records_from_section(qif, QIFType::Bank);
+                splits = retrieved_splits;
+                cleared = retrieved_cleared;
+                has_identifiers = false;
+                retrieved_records
+            },
+            ref p if p.ends_with(".ofx") || p.ends_with(".qfx") => {
+                let content = fs::read(p).map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", self.input_file, error)))?;
+                let mut retrieved_records = vec![];
+                let mut skipped_entries = 0;
+
+                let (statements, invalid_transactions) = OfxStatement::parse(&decode_text(&content))?;
+                invalid_rows = invalid_transactions;
+
+                for statement in statements {
+                    for mut transaction in statement.transactions {
+                        if fitids.values().any(|fitid| *fitid == transaction.fitid) {
+                            skipped_entries += 1;
+                            continue;
+                        }
+
+                        if let Some(id) = record_id_with_fitid_in_db(db, &transaction.fitid, &self.account)? {
+                            transaction.record.id = id;
+                        }
+
+                        cleared.push(transaction.record.id.clone());
+                        fitids.insert(transaction.record.id.clone(), transaction.fitid);
+                        retrieved_records.push(transaction.record);
+                    }
+
+                    ledger_balances.extend(statement.ledger_balance);
                 }
+
+                if skipped_entries > 0 {
+                    println!("Skipped {} entries that appear in the file more than once.", skipped_entries);
+                }
+
+                has_identifiers = false;
+
+                retrieved_records
+            },
+            ref p if JournalFormat::from_path(p).is_some() => {
+                let mut retrieved_records = vec![];
+                let mut transfers: Vec<JournalTransfer> = vec![];
+                let (entries, invalid_transactions) = journal_entries_from_file(p)?;
+                invalid_rows = invalid_transactions;
+
+                for entry in entries {
+                    if !entry.splits.is_empty() {
+                        splits.insert(entry.record.id.to_lowercase(), entry.splits);
+                    }
+
+                    if entry.is_transfer {
+                        transfers.push((entry.record.id.clone(), entry.record.transaction.date, signed_amount(&entry.record.transaction), entry.account.clone()));
+                    }
+
+                    if entry.status == Status::Cleared {
+                        cleared.push(entry.record.id.clone());
+                    }
+
+                    record_accounts.insert(entry.record.id.clone(), entry.account);
+                    retrieved_records.push(entry.record);
+                }
+
+                let (pairs, invalid_transfers) = self.transfer_pairs(db, transfers)?;
+                transfer_pairs = pairs;
+                invalid_rows.extend(invalid_transfers);
+
+                retrieved_records
             },
-            ref p if p.ends_with(".qif") => if let Ok(qif) = QIF::load_from_file(p, &DateFormat::MonthDayFullYear) {
-                records_from_section(qif, QIFType::Bank)
-            } else {
-                vec![]
-            }
             ref p if p.ends_with(".tsv") => {
-                if let Ok(retrieved_records) = Record::from_tsv_file(&source_path) {
-                    retrieved_records
-                } else {
-                    vec![]
+                let retrieved_records = Record::from_tsv_file(&source_path).map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", self.input_file, error)))?;
+                cleared = cleared_from_tsv(p, &retrieved_records);
+                retrieved_records
+            },
+            ref p if p.ends_with(".csv") => {
+                let profile = self.csv_profile(db)?;
+
+                if self.save_profile.is_some() && !self.dry_run {
+                    save_csv_profile_to_db(db, &profile)?;
                 }
+
+                let (retrieved_records, retrieved_invalid_rows) = profile.records_from_file(p)?;
+                invalid_rows = retrieved_invalid_rows;
+                has_identifiers = false;
+                retrieved_records
+            },
+            ref p if p.ends_with(".ods") => {
+                let (retrieved_records, retrieved_cleared, retrieved_invalid_rows) = records_from_ods(p)?;
+                cleared = retrieved_cleared;
+                invalid_rows = retrieved_invalid_rows;
+                retrieved_records
+            },
+            ref p if p.ends_with(".xlsx") => {
+                let (retrieved_records, retrieved_cleared, retrieved_invalid_rows) = records_from_xlsx(p)?;
+                cleared = retrieved_cleared;
+                invalid_rows = retrieved_invalid_rows;
+                retrieved_records
+            },
+            _ => return Err(CheckbookError::InvalidInput(format!("{} is not a supported file type.", self.input_file)))
+        };
+
+        // the opening balance of an export stands for entries before it, which would be counted twice if it were imported.
+        let opening_balance_ids: HashSet<String> = records.iter().filter(|record| is_opening_balance_id(&record.id) || fitids.get(&record.id).is_some_and(|fitid| is_opening_balance_id(fitid))).map(|record| record.id.clone()).collect();
+
+        if !opening_balance_ids.is_empty() {
+            records.retain(|record| !opening_balance_ids.contains(&record.id));
+            fitids.retain(|id, _| !opening_balance_ids.contains(id));
+            record_accounts.retain(|id, _| !opening_balance_ids.contains(id));
+
+            println!("Skipped {} opening balance entries written by an export.", opening_balance_ids.len());
+        }
+
+        let rules = load_rules_from_db(db)?;
+
+        for record in records.iter_mut() {
+            apply_rules(&rules, &mut record.transaction, splits.contains_key(&record.id.to_lowercase()));
+        }
+
+        if !has_identifiers {
+            match_stored_entries(db, &self.account, &mut records, &mut splits, &mut cleared, &mut fitids)?;
+        }
+
+        let comparisons = record_comparisons(db, &records, &splits, &cleared)?;
+
+        if self.dry_run {
+            preview(&records, &comparisons, &cleared, &invalid_rows);
+            return Ok(());
+        }
+
+        if let Some(error) = invalid_rows.into_iter().next() {
+            return Err(error);
+        }
+
+        let changed_ids: HashSet<String> = records.iter().zip(&comparisons).filter(|(_, comparison)| matches!(comparison, RecordComparison::Changed(_))).map(|(record, _)| record.id.to_lowercase()).collect();
+
+        match self.on_conflict {
+            ConflictPolicy::Fail if !changed_ids.is_empty() => return Err(CheckbookError::ConstraintViolation(format!("{} entries are already in the register with different details. Use --dry-run to see what would change, or --on-conflict to skip or overwrite them.", changed_ids.len()))),
+            ConflictPolicy::Skip if !changed_ids.is_empty() => {
+                records.retain(|record| !changed_ids.contains(&record.id.to_lowercase()));
+                splits.retain(|id, _| !changed_ids.contains(id));
+                record_accounts.retain(|id, _| !changed_ids.contains(&id.to_lowercase()));
+
+                println!("Skipped {} entries that differ from the register.", changed_ids.len());
             },
-            ref p if p.ends_with(".ods") => records_from_ods(p),
-            ref p if p.ends_with(".xlsx") => records_from_xlsx(p),
-            _ => vec![]
+            _ => ()
+        }
+
+        add_records_to_db(db, &records, &self.account)?;
+
+        // entries from journals go back to the account they were posted to, when the register has one by that name.
+        if self.account.is_none() {
+            for (id, account) in &record_accounts {
+                if account_exists_in_db(db, account)? {
+                    move_record_to_account(db, id, account)?;
+                }
+            }
+        }
+
+        for (first, second) in &transfer_pairs {
+            link_transfer_in_db(db, first, second)?;
+        }
+
+        for record in &records {
+            set_record_status_in_db(db, &record.id, &imported_status(record, &cleared))?;
+        }
+
+        for (id, fitid) in &fitids {
+            set_record_fitid_in_db(db, id, fitid)?;
+        }
+
+        for (id, record_splits) in splits {
+            replace_splits_in_db(db, &id, &record_splits)?;
+        }
+
+        for balance in ledger_balances {
+            let account = self.account.as_ref().map(|account| format!(" --account \"{}\"", account)).unwrap_or_default();
+
+            println!("Ledger Balance\t{:.2} as of {}", balance.amount, balance.date.format("%Y-%m-%d"));
+            println!("To reconcile against it, run: rcheckbook reconcile -d {} -b {:.2}{}", balance.date.format("%Y-%m-%d"), balance.amount, account);
+        }
+
+        Ok(())
+    }
+
+    /** pair up the halves of transfers read from a journal, which are posted through Equity:Transfers.
+     * The halves of a transfer are on the same day, for opposite amounts, in different accounts.
+     * A half without a match is only fine when the register already has it as a transfer,
+     * such as when a single account was exported, and otherwise cannot be imported.
+     */
+    fn transfer_pairs(&self, db: &Connection, transfers: Vec<JournalTransfer>) -> Result<TransferPairs, CheckbookError> {
+        let linked_ids = load_transfer_ids_from_db(db)?;
+        let mut unpaired: VecDeque<JournalTransfer> = transfers.into();
+        let mut pairs = vec![];
+        let mut invalid_transfers = vec![];
+
+        while let Some((id, date, amount, account)) = unpaired.pop_front() {
+            let other = unpaired.iter().position(|(_, other_date, other_amount, other_account)| *other_date == date && *other_amount == -amount && other_account.to_lowercase() != account.to_lowercase()).and_then(|index| unpaired.remove(index));
+
+            match other {
+                Some((other_id, _, _, other_account)) if self.register_account(db, &account)? != self.register_account(db, &other_account)? => pairs.push((id, other_id)),
+                Some((_, _, _, other_account)) => invalid_transfers.push(CheckbookError::Parse(format!("the transfer between {} and {} on {} would have both halves in the same account. Please add both accounts to the register, and import without --account.", account, other_account, date.format("%Y-%m-%d")))),
+                None if linked_ids.contains(&id.to_lowercase()) => (),
+                None => invalid_transfers.push(CheckbookError::Parse(format!("the transfer of {:.2} in {} on {} has no other half in the file.", amount, account, date.format("%Y-%m-%d"))))
+            }
+        }
+
+        Ok((pairs, invalid_transfers))
+    }
+
+    /// retrieve the account an entry from a journal is imported into, where None is the Default account.
+    fn register_account(&self, db: &Connection, journal_account: &str) -> Result<Option<String>, CheckbookError> {
+        if let Some(account) = &self.account {
+            return Ok(Some(account.to_lowercase()));
+        }
+
+        Ok(if account_exists_in_db(db, journal_account)? { Some(journal_account.to_lowercase()) } else { None })
+    }
+
+    /** retrieve the layout of a CSV file from the saved profile and the options given.
+     * Options that are given override those of the profile.
+     */
+    fn csv_profile(&self, db: &Connection) -> Result<CsvProfile, CheckbookError> {
+        let stored_profile = match &self.profile {
+            Some(name) => Some(retrieve_csv_profile_from_db(db, name)?),
+            None => None
+        };
+
+        let columns = match (&self.columns, &stored_profile) {
+            (Some(mapping), _) => CsvColumns::parse(mapping)?,
+            (None, Some(profile)) => profile.columns.clone(),
+            (None, None) => return Err(CheckbookError::InvalidInput("Please give the columns of the CSV file with --columns, or a saved profile with --profile.".to_string()))
+        };
+
+        let decimal_separator = self.decimal_separator.or(stored_profile.as_ref().map(|profile| profile.decimal_separator)).unwrap_or('.');
+
+        if !matches!(decimal_separator, '.' | ',') {
+            return Err(CheckbookError::InvalidInput(format!("{} cannot be used as a decimal separator. Please use a period or a comma.", decimal_separator)));
+        }
+
+        Ok(CsvProfile {
+            name: self.save_profile.clone().or(self.profile.clone()).unwrap_or_default(),
+            columns,
+            date_format: self.date_format.clone().or(stored_profile.as_ref().map(|profile| profile.date_format.clone())).unwrap_or(DEFAULT_DATE_FORMAT.to_string()),
+            delimiter: self.delimiter.or(stored_profile.as_ref().map(|profile| profile.delimiter)).unwrap_or(','),
+            skip_rows: self.skip_rows.or(stored_profile.as_ref().map(|profile| profile.skip_rows)).unwrap_or(0),
+            sign: self.sign.clone().or(stored_profile.map(|profile| profile.sign)).unwrap_or(SignConvention::DepositsPositive),
+            decimal_separator
+        })
+    }
+}
+
+/// retrieve the status a record is imported with, where cleared records are those the file marks as cleared.
+fn imported_status(record: &Record, cleared: &[String]) -> Status {
+    if cleared.contains(&record.id) {
+        Status::Cleared
+    } else {
+        Status::from_reconciled(record.transaction.is_reconciled)
+    }
+}
+
+/** give records from files without identifiers, such as bank downloads, the identifier of the entry they match in the register.
+ * OFX entries have already been matched by the identifier the bank gave them,
+ * and the rest are matched by date, amount, and vendor, so that importing the same file again does not add everything twice.
+ * Files like these say nothing about categories and little about status,
+ * so matched records keep the category of the entry when they have none, and never lower its status.
+ */
+fn match_stored_entries(db: &Connection, account: &Option<String>, records: &mut [Record], splits: &mut HashMap<String, Vec<Split>>, cleared: &mut Vec<String>, fitids: &mut HashMap<String, String>) -> Result<(), CheckbookError> {
+    let stored_entries = match account {
+        Some(name) if !account_exists_in_db(db, name)? => vec![],
+        _ => load_ledger_from_db(db, account)?
+    };
+
+    let entries_by_id: HashMap<String, &LedgerEntry> = stored_entries.iter().map(|entry| (entry.record.id.to_lowercase(), entry)).collect();
+    let matched_ids: HashSet<String> = records.iter().map(|record| record.id.to_lowercase()).filter(|id| entries_by_id.contains_key(id)).collect();
+    let matching_details = |record: &Record| (record.transaction.date, format!("{:.2}", signed_amount(&record.transaction)), record.transaction.vendor.trim().to_lowercase());
+    let mut entries_by_details: HashMap<(NaiveDate, String, String), VecDeque<&LedgerEntry>> = HashMap::new();
+
+    for entry in stored_entries.iter().filter(|entry| !matched_ids.contains(&entry.record.id.to_lowercase())) {
+        entries_by_details.entry(matching_details(&entry.record)).or_default().push_back(entry);
+    }
+
+    for record in records.iter_mut() {
+        let entry = match entries_by_id.get(&record.id.to_lowercase()) {
+            Some(entry) => *entry,
+            None => {
+                let Some(entry) = entries_by_details.get_mut(&matching_details(record)).and_then(VecDeque::pop_front) else {
+                    continue;
+                };
+
+                if let Some(record_splits) = splits.remove(&record.id.to_lowercase()) {
+                    splits.insert(entry.record.id.to_lowercase(), record_splits);
+                }
+
+                if let Some(fitid) = fitids.remove(&record.id) {
+                    fitids.insert(entry.record.id.clone(), fitid);
+                }
+
+                for cleared_id in cleared.iter_mut().filter(|cleared_id| **cleared_id == record.id) {
+                    *cleared_id = entry.record.id.clone();
+                }
+
+                record.id = entry.record.id.clone();
+                entry
+            }
         };
 
-        add_records_to_db(&self.file_path, &records)
+        if record.transaction.category.is_none() {
+            record.transaction.category = entry.record.transaction.category.clone();
+        }
+
+        match entry.status {
+            Status::Reconciled => {
+                record.transaction.is_reconciled = true;
+                cleared.retain(|cleared_id| *cleared_id != record.id);
+            },
+            Status::Cleared if !record.transaction.is_reconciled && !cleared.contains(&record.id) => cleared.push(record.id.clone()),
+            _ => ()
+        }
+    }
+
+    Ok(())
+}
+
+/** compare each record being imported with what the register has, in the order they are imported.
+ * A record that appears more than once in the file is compared with its earlier appearance,
+ * since that is what it would be written over.
+ */
+fn record_comparisons(db: &Connection, records: &[Record], splits: &HashMap<String, Vec<Split>>, cleared: &[String]) -> Result<Vec<RecordComparison>, CheckbookError> {
+    let mut stored_splits = load_splits_from_db(db)?;
+
+    let mut stored_entries: HashMap<String, (Record, Status, Vec<Split>)> = load_ledger_from_db(db, &None)?.into_iter().map(|entry| {
+        let id = entry.record.id.to_lowercase();
+        let record_splits = stored_splits.remove(&id).unwrap_or_default();
+
+        (id, (entry.record, entry.status, record_splits))
+    }).collect();
+
+    let mut comparisons = vec![];
+
+    for record in records {
+        let id = record.id.to_lowercase();
+        let status = imported_status(record, cleared);
+        let record_splits = splits.get(&id);
+
+        let comparison = RecordComparison::from(stored_entries.get(&id).map(|(stored_record, stored_status, stored_splits)| (stored_record, stored_status, stored_splits.as_slice())), (record, &status, record_splits.map(Vec::as_slice)));
+
+        let splits_after_import = record_splits.cloned().or(stored_entries.get(&id).map(|(_, _, stored_splits)| stored_splits.clone())).unwrap_or_default();
+        stored_entries.insert(id, (record.clone(), status, splits_after_import));
+
+        comparisons.push(comparison);
     }
+
+    Ok(comparisons)
+}
+
+/** show how each record would be imported, followed by the rows that could not be read and a count of each.
+ * Changed records are followed by an indented line for each field that would change.
+ */
+fn preview(records: &[Record], comparisons: &[RecordComparison], cleared: &[String], invalid_rows: &[CheckbookError]) {
+    for (record, comparison) in records.iter().zip(comparisons) {
+        println!("{}\t{}", comparison.as_str(), record_with_status(record, &imported_status(record, cleared)));
+
+        if let RecordComparison::Changed(changes) = comparison {
+            for change in changes {
+                println!("\t{}", change);
+            }
+        }
+    }
+
+    for error in invalid_rows {
+        println!("invalid\t{}", error);
+    }
+
+    let count = |kind: &str| comparisons.iter().filter(|comparison| comparison.as_str() == kind).count();
+
+    println!("{} new, {} identical, {} changed, {} invalid. Nothing was imported, since this was a dry run.", count("new"), count("identical"), count("changed"), invalid_rows.len());
 }
 
 fn qif_transaction_to_transaction(transaction: &QIFTransaction) -> Transaction {
-    Transaction::builder()
-    .set_date(&transaction.date.format(&DateFormat::FullYearMonthDay.chrono_str()).to_string())
+    let mut converted_transaction = Transaction::builder()
+    .set_date(&transaction.date.format(DateFormat::FullYearMonthDay.chrono_str()).to_string())
     .set_check_number(transaction.check_number.unwrap_or(0))
-    .set_category(&transaction.category.clone().unwrap_or(String::default()))
+    .set_category(&qif_category(&transaction.category))
     .set_vendor(&transaction.vendor)
     .set_memo(&transaction.memo)
-    .set_amount_and_type(transaction.amount)
-    .set_is_reconciled(if let Some(status) = transaction.status.clone() {
-        status == TransactionStatus::Reconciled
-    } else {
-        false
-    })
-    .build()
+    .set_is_reconciled(transaction.status == Some(TransactionStatus::Reconciled))
+    .build();
+
+    set_signed_amount(&mut converted_transaction, from_float(transaction.amount));
+
+    converted_transaction
 }
 
-fn records_from_section(qif: QIF, section: QIFType) -> Vec<Record> {
-    match section {
-        QIFType::Bank => if let Some(bank) = qif.bank {
-            bank.transactions.into_iter()
-            .map(|t| qif_transaction_to_transaction(&t))
-            .map(|t| Record::from("", t))
-            .collect()
-        } else {
-            vec![]
-        },
-        QIFType::Cash => if let Some(cash) = qif.cash {
-            cash.transactions.into_iter()
-            .map(|t| qif_transaction_to_transaction(&t))
-            .map(|t| Record::from("", t))
-            .collect()
-        } else {
-            vec![]
+/** retrieve the category path of a QIF transaction or split, such as Utilities:Electric.
+ * QIF files can add a class after a slash, which the register has no place for, so it is dropped.
+ */
+fn qif_category(category: &Option<String>) -> String {
+    let path = category.as_deref().unwrap_or_default();
+
+    normalized_path(path.split_once('/').map(|(path, _)| path).unwrap_or(path))
+}
+
+/** rewrite the status of each QIF transaction into the markers the qif crate understands.
+ * Files mark cleared transactions with c and reconciled ones with * or X,
+ * but the crate reads X as cleared, and drops c altogether.
+ */
+fn normalized_qif_statuses(content: &str) -> String {
+    content.lines().map(|line| match line.strip_prefix('C') {
+        Some(marker) => match Status::from_letter(marker) {
+            Status::Cleared => "CX",
+            Status::Reconciled => "C*",
+            Status::Uncleared => "C"
+        }.to_string(),
+        None => line.to_string()
+    }).collect::<Vec<String>>().join("\n")
+}
+
+/// retrieve the records in the given section, along with the splits of each record and the identifiers of cleared records.
+fn records_from_section(qif: QIF, section: QIFType) -> (Vec<Record>, HashMap<String, Vec<Split>>, Vec<String>) {
+    let transactions = match section {
+        QIFType::Bank => qif.bank,
+        QIFType::Cash => qif.cash,
+        QIFType::CreditCard => qif.credit_card,
+        QIFType::Liability => qif.liability,
+        QIFType::Asset => qif.asset
+    }.map(|section| section.transactions).unwrap_or(vec![]);
+
+    let mut records = vec![];
+    let mut splits: HashMap<String, Vec<Split>> = HashMap::new();
+    let mut cleared = vec![];
+
+    for transaction in transactions {
+        let record = Record::from("", qif_transaction_to_transaction(&transaction));
+
+        if transaction.status == Some(TransactionStatus::Cleared) {
+            cleared.push(record.id.clone());
+        }
+
+        if !transaction.splits.is_empty() {
+            splits.insert(record.id.to_lowercase(), transaction.splits.iter().map(|split| Split::from(&qif_category(&split.category), from_float(split.amount), &split.memo)).collect());
+        }
+
+        records.push(record);
+    }
+
+    (records, splits, cleared)
+}
+
+/// retrieve the entries in a ledger, hledger, or beancount journal, depending on the extension, along with the transactions that could not be read.
+fn journal_entries_from_file(p: &str) -> Result<(Vec<JournalEntry>, Vec<CheckbookError>), CheckbookError> {
+    let Some(format) = JournalFormat::from_path(p) else {
+        return Err(CheckbookError::InvalidInput(format!("{} is not a ledger, hledger, or beancount file.", p)));
+    };
+
+    let content = fs::read(p).map_err(|error| CheckbookError::Parse(format!("could not read {}: {}", p, error)))?;
+
+    Ok(entries_from_journal(&decode_text(&content), format))
+}
+
+/** retrieve the splits stored alongside records in a bcheck file.
+ * Splits are kept in each transaction under a splits key, 
+ * which is ignored by anything that does not know about it.
+ */
+fn splits_from_bcheck(p: &str, records: &Vec<Record>) -> HashMap<String, Vec<Split>> {
+    let mut splits: HashMap<String, Vec<Split>> = HashMap::new();
+
+    if let Ok(content) = fs::read_to_string(p) && let Ok(serde_json::Value::Array(entries)) = serde_json::from_str::<serde_json::Value>(&content) {
+        for (entry, record) in entries.iter().zip(records) {
+            if let Ok(record_splits) = serde_json::from_value::<Vec<Split>>(entry["transaction"]["splits"].clone()) && !record_splits.is_empty() {
+                splits.insert(record.id.to_lowercase(), record_splits);
+            }
+        }
+    }
+
+    splits
+}
+
+/** retrieve the identifiers of the records in a bcheck file that are marked as cleared.
+ * Like splits, this is kept in each transaction under a cleared key.
+ */
+fn cleared_from_bcheck(p: &str, records: &[Record]) -> Vec<String> {
+    let mut cleared = vec![];
+
+    if let Ok(content) = fs::read_to_string(p) && let Ok(serde_json::Value::Array(entries)) = serde_json::from_str::<serde_json::Value>(&content) {
+        for (entry, record) in entries.iter().zip(records) {
+            if entry["transaction"]["cleared"] == serde_json::Value::Bool(true) {
+                cleared.push(record.id.clone());
+            }
         }
-        QIFType::CreditCard => if let Some(credit_card) = qif.credit_card {
-            credit_card.transactions.into_iter()
-            .map(|t| qif_transaction_to_transaction(&t))
-            .map(|t| Record::from("", t))
-            .collect()
-        } else {
-            vec![]
-        },
-        QIFType::Liability => if let Some(liability) = qif.liability {
-            liability.transactions.into_iter()
-            .map(|t| qif_transaction_to_transaction(&t))
-            .map(|t| Record::from("", t))
-            .collect()
-        } else {
-            vec![]
-        },
-        QIFType::Asset => if let Some(asset) = qif.asset {
-            asset.transactions.into_iter()
-            .map(|t| qif_transaction_to_transaction(&t))
-            .map(|t| Record::from("", t))
-            .collect()
-        } else {
-            vec![]
-        },
     }
+
+    cleared
 }
 
-fn record_from_xlsx_row(row: &[Data]) -> Result<Record, ImportError> {
+/** retrieve the identifiers of the records in a TSV file that are marked as cleared.
+ * bcheck only reads whether records are reconciled, so the status column is read again here.
+ */
+fn cleared_from_tsv(p: &str, records: &[Record]) -> Vec<String> {
+    let content = fs::read_to_string(p).unwrap_or_default();
+
+    content.lines().zip(records).filter(|(line, _)| Status::from_letter(line.split('\t').nth(3).unwrap_or_default()) == Status::Cleared).map(|(_, record)| record.id.clone()).collect()
+}
+
+fn record_from_xlsx_row(row: &[Data]) -> Result<(Record, Status), CheckbookError> {
     let mut id = "";
     let mut date = "";
     let mut check_number = 0;
-    let mut is_reconciled = false;
+    let mut status = Status::Uncleared;
     let mut category = "";
     let mut vendor = "";
     let mut memo = "";
-    let mut credit = 0.0;
-    let mut withdrawal = 0.0;
+    let mut credit = D64::ZERO;
+    let mut withdrawal = D64::ZERO;
 
     for (column_index, data) in row.iter().enumerate() {
         if let calamine::Data::Empty = data { 
@@ -136,13 +616,11 @@ fn record_from_xlsx_row(row: &[Data]) -> Result<Record, ImportError> {
                 1 => if let calamine::Data::String(record_date) = data {
                     date = record_date;
                 },
-                2 => if let calamine::Data::String(record_check_number) = data {
-                    if !record_check_number.is_empty() {
-                        check_number = record_check_number.parse::<u32>().expect("value must be a number 0 or greater")
-                    }
+                2 => if let calamine::Data::String(record_check_number) = data && !record_check_number.is_empty() {
+                    check_number = record_check_number.parse::<u32>().map_err(|_| CheckbookError::Parse(format!("check number {} must be a number 0 or greater.", record_check_number)))?
                 },
                 3 => if let calamine::Data::String(record_reconciled) = data {
-                    is_reconciled = record_reconciled.to_uppercase() == "Y"
+                    status = Status::from_letter(record_reconciled)
                 },
                 4 => if let calamine::Data::String(record_category) = data {
                     category = record_category;
@@ -154,68 +632,87 @@ fn record_from_xlsx_row(row: &[Data]) -> Result<Record, ImportError> {
                     memo = record_memo;
                 },
                 7 => if let calamine::Data::Float(record_deposit) = data {
-                    credit = record_deposit.to_owned()
+                    credit = from_float(record_deposit.to_owned())
                 },
                 8 => if let calamine::Data::Float(record_withdrawal) = data {
-                    withdrawal = record_withdrawal.to_owned()
+                    withdrawal = from_float(record_withdrawal.to_owned())
                 },
                 _ => ()
             };
         }
     }
 
-    if credit > 0.0 && withdrawal > 0.0 {
-        return Err(ImportError::TransactionTypeParsingError);
+    if credit > D64::ZERO && withdrawal > D64::ZERO {
+        return Err(CheckbookError::TransactionTypeParsingError);
     }
 
-    let amount = if credit > 0.0 {
+    let amount = if credit > D64::ZERO {
         credit
     } else {
-        if withdrawal > 0.0 { withdrawal*-1.0 } else { withdrawal }
+        if withdrawal > D64::ZERO { -withdrawal } else { withdrawal }
     };
 
-    let transaction = Transaction::builder()
+    let mut transaction = Transaction::builder()
     .set_date(date)
     .set_check_number(check_number)
-    .set_category(category)
+    .set_category(&normalized_path(category))
     .set_vendor(vendor)
     .set_memo(memo)
-    .set_amount_and_type(amount)
-    .set_is_reconciled(is_reconciled)
+    .set_is_reconciled(status == Status::Reconciled)
     .build();
 
-    Ok(Record::from(id, transaction))
+    set_signed_amount(&mut transaction, amount);
+
+    Ok((Record::from(id, transaction), status))
 }
 
-fn records_from_xlsx(p: &str) -> Vec<Record> {
+/// a half of a transfer read from a journal, as the identifier, date, and amount of the entry, along with the account it was posted to.
+type JournalTransfer = (String, NaiveDate, D64, String);
+
+/// the identifiers of the transfers paired up from a journal, along with the halves that could not be paired.
+type TransferPairs = (Vec<(String, String)>, Vec<CheckbookError>);
+
+/// the records in a spreadsheet, the identifiers of those marked as cleared, and the rows that could not be read.
+type SpreadsheetRecords = (Vec<Record>, Vec<String>, Vec<CheckbookError>);
+
+/// retrieve the records in the first sheet of the workbook, along with the identifiers of cleared records and the rows that could not be read.
+fn records_from_xlsx(p: &str) -> Result<SpreadsheetRecords, CheckbookError> {
     let mut records = vec![];
-    let mut workbook: Xlsx<_> = open_workbook(p).expect("Could not read workbook");
-    let range = workbook.worksheet_range_at(0).unwrap().expect("Could not read sheet");
+    let mut cleared = vec![];
+    let mut invalid_rows = vec![];
+    let mut workbook: Xlsx<_> = open_workbook(p).map_err(|error| CheckbookError::Parse(format!("could not read workbook: {}", error)))?;
+    let range = workbook.worksheet_range_at(0).ok_or(CheckbookError::Parse("workbook has no sheets.".to_string()))?.map_err(|error| CheckbookError::Parse(format!("could not read sheet: {}", error)))?;
 
     for (row_index, row) in range.rows().enumerate() {
         if row_index == 0 {
             continue;
         } else {
             match record_from_xlsx_row(row) {
-                Ok(record) => records.push(record),
-                Err(error) => println!("{}", error)
+                Ok((record, status)) => {
+                    if status == Status::Cleared {
+                        cleared.push(record.id.clone());
+                    }
+
+                    records.push(record);
+                },
+                Err(error) => invalid_rows.push(CheckbookError::Parse(format!("row {}: {}", row_index + 1, error)))
             }
         }
     }
 
-    records
+    Ok((records, cleared, invalid_rows))
 }
 
-fn record_from_ods_row(row: &[Data]) -> Result<Record, ImportError> {
+fn record_from_ods_row(row: &[Data]) -> Result<(Record, Status), CheckbookError> {
     let mut id = "";
     let mut date = "";
     let mut check_number = 0;
-    let mut is_reconciled = false;
+    let mut status = Status::Uncleared;
     let mut category = "";
     let mut vendor = "";
     let mut memo = "";
-    let mut credit = 0.0;
-    let mut withdrawal = 0.0;
+    let mut credit = D64::ZERO;
+    let mut withdrawal = D64::ZERO;
 
     for (column_index, data) in row.iter().enumerate() {
         if let calamine::Data::Empty = data { 
@@ -228,13 +725,11 @@ fn record_from_ods_row(row: &[Data]) -> Result<Record, ImportError> {
                 1 => if let calamine::Data::String(record_date) = data {
                     date = record_date;
                 },
-                2 => if let calamine::Data::String(record_check_number) = data {
-                    if !record_check_number.is_empty() {
-                        check_number = record_check_number.parse::<u32>().expect("value must be a number 0 or greater")
-                    }
+                2 => if let calamine::Data::String(record_check_number) = data && !record_check_number.is_empty() {
+                    check_number = record_check_number.parse::<u32>().map_err(|_| CheckbookError::Parse(format!("check number {} must be a number 0 or greater.", record_check_number)))?
                 },
                 3 => if let calamine::Data::String(record_reconciled) = data {
-                    is_reconciled = record_reconciled.to_uppercase() == "Y"
+                    status = Status::from_letter(record_reconciled)
                 },
                 4 => if let calamine::Data::String(record_category) = data {
                     category = record_category;
@@ -245,60 +740,114 @@ fn record_from_ods_row(row: &[Data]) -> Result<Record, ImportError> {
                 6 => if let calamine::Data::String(record_memo) = data {
                     memo = record_memo;
                 },
-                7 => if let calamine::Data::String(record_deposit) = data {
-                    if let Ok(credit_amount) = record_deposit.parse::<f64>() {
-                        credit = credit_amount
-                    }
+                7 => if let calamine::Data::String(record_deposit) = data && let Ok(credit_amount) = record_deposit.parse::<D64>() {
+                    credit = credit_amount
                 },
-                8 => if let calamine::Data::String(record_withdrawal) = data {
-                    if let Ok(withdrawal_amount) = record_withdrawal.parse::<f64>() {
-                        withdrawal = withdrawal_amount
-                    }
+                8 => if let calamine::Data::String(record_withdrawal) = data && let Ok(withdrawal_amount) = record_withdrawal.parse::<D64>() {
+                    withdrawal = withdrawal_amount
                 },
                 _ => ()
             };
         }
     }
 
-    if credit > 0.0 && withdrawal > 0.0 {
-        return Err(ImportError::TransactionTypeParsingError);
+    if credit > D64::ZERO && withdrawal > D64::ZERO {
+        return Err(CheckbookError::TransactionTypeParsingError);
     }
 
-    let amount = if credit > 0.0 {
+    let amount = if credit > D64::ZERO {
         credit
     } else {
-        if withdrawal > 0.0 { withdrawal*-1.0 } else { withdrawal }
+        if withdrawal > D64::ZERO { -withdrawal } else { withdrawal }
     };
 
-    let transaction = Transaction::builder()
+    let mut transaction = Transaction::builder()
     .set_date(date)
     .set_check_number(check_number)
-    .set_category(category)
+    .set_category(&normalized_path(category))
     .set_vendor(vendor)
     .set_memo(memo)
-    .set_amount_and_type(amount)
-    .set_is_reconciled(is_reconciled)
+    .set_is_reconciled(status == Status::Reconciled)
     .build();
 
-    Ok(Record::from(id, transaction))
+    set_signed_amount(&mut transaction, amount);
+
+    Ok((Record::from(id, transaction), status))
 }
 
-fn records_from_ods(p: &str) -> Vec<Record> {
+/// retrieve the records in the first sheet of the workbook, along with the identifiers of cleared records and the rows that could not be read.
+fn records_from_ods(p: &str) -> Result<SpreadsheetRecords, CheckbookError> {
     let mut records = vec![];
-    let mut workbook: Ods<_> = open_workbook(p).expect("Could not read workbook");
-    let range = workbook.worksheet_range_at(0).unwrap().expect("Could not read sheet");
+    let mut cleared = vec![];
+    let mut invalid_rows = vec![];
+    let mut workbook: Ods<_> = open_workbook(p).map_err(|error| CheckbookError::Parse(format!("could not read workbook: {}", error)))?;
+    let range = workbook.worksheet_range_at(0).ok_or(CheckbookError::Parse("workbook has no sheets.".to_string()))?.map_err(|error| CheckbookError::Parse(format!("could not read sheet: {}", error)))?;
     for (row_index, row) in range.rows().enumerate() {
         if row_index == 0 {
             continue;
         } else {
             match record_from_ods_row(row) {
-                Ok(record) => records.push(record),
-                Err(error) => println!("{}", error)
+                Ok((record, status)) => {
+                    if status == Status::Cleared {
+                        cleared.push(record.id.clone());
+                    }
+
+                    records.push(record);
+                },
+                Err(error) => invalid_rows.push(CheckbookError::Parse(format!("row {}: {}", row_index + 1, error)))
             }
         }
     }
 
-    records
+    Ok((records, cleared, invalid_rows))
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// a transfer from checking to savings, as an export writes it.
+    const TRANSFER_JOURNAL: &str = "2026-10-05 Transfer to Savings
+    ; id: DC4033CC-C4F5-4698-A1D8-FA55F4845726
+    Assets:Checking  $-200.00
+    Equity:Transfers  $200.00
+
+2026-10-05 Transfer from Checking
+    ; id: 4DA9E829-D26A-4DB4-B110-5C652223CBC0
+    Assets:Savings  $200.00
+    Equity:Transfers  $-200.00
+";
+
+    #[test]
+    fn links_transfers_from_journals() {
+        let directory = env::temp_dir().join(format!("rcheckbook-journal-transfers-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+        let journal = directory.join("transfers.ledger");
+        let j = journal.to_str().expect("temporary directory is not valid UTF-8");
+
+        let db = copy_database_if_not_exists(p).expect("could not create database");
+        fs::write(&journal, TRANSFER_JOURNAL).expect("could not write journal");
+
+        // both halves would end up in the Default account while the register lacks the accounts they were posted to.
+        assert!(matches!(Import::try_parse_from(["import", p, "-i", j]).expect("could not parse arguments").run(), Err(CheckbookError::Parse(_))));
+
+        add_account_to_db(&db, "Checking").expect("could not add account");
+        add_account_to_db(&db, "Savings").expect("could not add account");
+        drop(db);
+
+        Import::try_parse_from(["import", p, "-i", j]).expect("could not parse arguments").run().expect("could not import journal");
+
+        let db = open_database(p).expect("could not open database");
+        let mut transfers: Vec<(String, String)> = db.prepare("SELECT id, transfer FROM trades").and_then(|mut statement| statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect()).expect("could not read transfers");
+        transfers.sort();
+
+        assert_eq!(transfers, vec![("4DA9E829-D26A-4DB4-B110-5C652223CBC0".to_string(), "DC4033CC-C4F5-4698-A1D8-FA55F4845726".to_string()), ("DC4033CC-C4F5-4698-A1D8-FA55F4845726".to_string(), "4DA9E829-D26A-4DB4-B110-5C652223CBC0".to_string())]);
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+}
@@ -0,0 +1,520 @@
+use bcheck::{ Record, Transaction };
+use chrono::NaiveDate;
+use fastnum::D64;
+use crate::{ amount::*, category_path::normalized_path, errors::CheckbookError, split::Split, status::Status };
+
+/// the category used in account names for entries without one.
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// the account transfers are posted through, so that each side of a transfer stays its own transaction.
+const TRANSFERS_ACCOUNT: &str = "Equity:Transfers";
+
+/// the currency amounts are written in, since the register does not keep track of one.
+const CURRENCY: &str = "USD";
+
+/// Represents the plain text accounting formats the register can be exported to and imported from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JournalFormat {
+    Ledger,
+    Hledger,
+    Beancount
+}
+
+impl JournalFormat {
+    /// retrieve the format of the file at the given path, which is told by its extension.
+    pub fn from_path(p: &str) -> Option<Self> {
+        if p.ends_with(".ledger") {
+            Some(Self::Ledger)
+        } else if p.ends_with(".journal") {
+            Some(Self::Hledger)
+        } else if p.ends_with(".beancount") {
+            Some(Self::Beancount)
+        } else {
+            None
+        }
+    }
+}
+
+/// Represents an entry as a transaction in a journal, along with what bcheck cannot keep track of.
+pub struct JournalEntry {
+    pub record: Record,
+    pub status: Status,
+    pub splits: Vec<Split>,
+    pub account: String,
+    pub is_transfer: bool
+}
+
+/** Represents a single posting of a transaction, where a missing amount is worked out from the others.
+ * The name is the account or category the posting is for, as the register has it,
+ * since beancount account names cannot hold every character.
+ */
+struct Posting {
+    account: String,
+    amount: Option<D64>,
+    name: Option<String>,
+    memo: String
+}
+
+impl JournalEntry {
+    /** retrieve the postings of the entry.
+     * The first posting is to the asset account the entry is in,
+     * and the rest are to an expense or income account for its category, or for each split.
+     */
+    fn postings(&self) -> Vec<Posting> {
+        let amount = signed_amount(&self.record.transaction);
+
+        let mut postings = vec![Posting {
+            account: format!("Assets:{}", self.account),
+            amount: Some(amount),
+            name: Some(self.account.clone()),
+            memo: String::new()
+        }];
+
+        if !self.splits.is_empty() {
+            for split in &self.splits {
+                postings.push(Posting {
+                    account: category_account(&split.category, split.amount),
+                    amount: Some(-split.amount),
+                    name: split.category.clone(),
+                    memo: split.memo.clone()
+                });
+            }
+        } else if self.is_transfer && self.record.transaction.category.is_none() {
+            postings.push(Posting {
+                account: TRANSFERS_ACCOUNT.to_string(),
+                amount: Some(-amount),
+                name: None,
+                memo: String::new()
+            });
+        } else {
+            postings.push(Posting {
+                account: category_account(&self.record.transaction.category, amount),
+                amount: Some(-amount),
+                name: self.record.transaction.category.clone(),
+                memo: String::new()
+            });
+        }
+
+        postings
+    }
+
+    fn to_ledger(&self) -> String {
+        let transaction = &self.record.transaction;
+
+        let flag = match self.status {
+            Status::Reconciled => " *",
+            Status::Cleared => " !",
+            Status::Uncleared => ""
+        };
+
+        let code = transaction.check_number.filter(|check_number| *check_number > 0).map(|check_number| format!(" ({})", check_number)).unwrap_or_default();
+        let memo = if transaction.memo.is_empty() { String::new() } else { format!("  ; {}", transaction.memo) };
+
+        let mut text = format!("{}{}{} {}{}\n    ; id: {}\n", transaction.date.format("%Y-%m-%d"), flag, code, transaction.vendor, memo, self.record.id);
+
+        for posting in self.postings() {
+            let amount = posting.amount.map(|amount| format!("${:.2}", amount)).unwrap_or_default();
+            let memo = if posting.memo.is_empty() { String::new() } else { format!("  ; {}", posting.memo) };
+
+            let line = format!("    {}  {}{}\n", ledger_account(&posting.account), amount, memo);
+            text.push_str(&line);
+        }
+
+        text
+    }
+
+    fn to_beancount(&self) -> String {
+        let transaction = &self.record.transaction;
+        let flag = if let Status::Reconciled = self.status { "*" } else { "!" };
+
+        let mut text = format!("{} {} {} {}\n  id: {}\n", transaction.date.format("%Y-%m-%d"), flag, quoted(&transaction.vendor), quoted(&transaction.memo), quoted(&self.record.id));
+
+        if let Some(check_number) = transaction.check_number.filter(|check_number| *check_number > 0) {
+            let check = format!("  check: {}\n", quoted(&check_number.to_string()));
+            text.push_str(&check);
+        }
+
+        if let Status::Cleared = self.status {
+            text.push_str("  status: \"cleared\"\n");
+        }
+
+        for (index, posting) in self.postings().into_iter().enumerate() {
+            let account = beancount_account(&posting.account);
+            let amount = posting.amount.map(|amount| format!("{:.2} {}", amount, CURRENCY)).unwrap_or_default();
+
+            let line = format!("  {}  {}\n", account, amount);
+            text.push_str(&line);
+
+            // account names are limited to letters, numbers, and dashes, so names that had to change are kept as they were.
+            if let Some(name) = posting.name.filter(|_| account != posting.account) {
+                let key = if index == 0 { "account" } else { "category" };
+
+                let name_entry = format!("    {}: {}\n", key, quoted(&name));
+                text.push_str(&name_entry);
+            }
+
+            if !posting.memo.is_empty() {
+                let memo = format!("    memo: {}\n", quoted(&posting.memo));
+                text.push_str(&memo);
+            }
+        }
+
+        text
+    }
+}
+
+/** present entries as a journal in the given format.
+ * ledger and hledger read the same format, so they are written the same way.
+ * Beancount only accepts postings to accounts that have been opened,
+ * so every account used is opened on the date of the earliest entry.
+ */
+pub fn entries_to_journal(entries: &[JournalEntry], format: JournalFormat) -> String {
+    let mut journal = String::new();
+
+    if let JournalFormat::Beancount = format {
+        let mut accounts: Vec<String> = vec![];
+
+        for entry in entries {
+            for posting in entry.postings() {
+                let account = beancount_account(&posting.account);
+
+                if !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+        }
+
+        if let Some(opened_on) = entries.iter().map(|entry| entry.record.transaction.date).min() {
+            for account in accounts {
+                let directive = format!("{} open {}\n", opened_on.format("%Y-%m-%d"), account);
+                journal.push_str(&directive);
+            }
+        }
+    }
+
+    for entry in entries {
+        let transaction = match format {
+            JournalFormat::Beancount => entry.to_beancount(),
+            _ => entry.to_ledger()
+        };
+
+        if !journal.is_empty() {
+            journal.push('\n');
+        }
+
+        journal.push_str(&transaction);
+    }
+
+    journal
+}
+
+/** retrieve the entries in a journal of the given format, along with the transactions that could not be read.
+ * Only transactions are read, while directives such as account declarations and prices are passed over.
+ * The first posting to an asset or liability account is taken as the side in the register,
+ * and the rest give the category, or the splits when there is more than one.
+ */
+pub fn entries_from_journal(content: &str, format: JournalFormat) -> (Vec<JournalEntry>, Vec<CheckbookError>) {
+    let mut entries = vec![];
+    let mut invalid_transactions = vec![];
+    let mut current: Option<(usize, JournalTransaction)> = None;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        let is_indented = line.starts_with(' ') || line.starts_with('\t');
+
+        if trimmed.is_empty() || !is_indented {
+            if let Some((header_line, transaction)) = current.take() {
+                match transaction.entry() {
+                    Ok(entry) => entries.push(entry),
+                    Err(error) => invalid_transactions.push(CheckbookError::Parse(format!("line {}: {}", header_line, error)))
+                }
+            }
+
+            if !trimmed.is_empty() {
+                match JournalTransaction::from_header(line, format) {
+                    Ok(transaction) => current = transaction.map(|transaction| (line_index + 1, transaction)),
+                    Err(error) => invalid_transactions.push(CheckbookError::Parse(format!("line {}: {}", line_index + 1, error)))
+                }
+            }
+        } else if let Some((_, transaction)) = current.as_mut() && let Err(error) = transaction.add_line(trimmed, format) {
+            // the rest of the transaction is passed over, since it cannot be imported without this posting.
+            invalid_transactions.push(CheckbookError::Parse(format!("line {}: {}", line_index + 1, error)));
+            current = None;
+        }
+    }
+
+    if let Some((header_line, transaction)) = current {
+        match transaction.entry() {
+            Ok(entry) => entries.push(entry),
+            Err(error) => invalid_transactions.push(CheckbookError::Parse(format!("line {}: {}", header_line, error)))
+        }
+    }
+
+    (entries, invalid_transactions)
+}
+
+/// Represents a transaction as it is being read from a journal.
+struct JournalTransaction {
+    date: NaiveDate,
+    status: Status,
+    check_number: u32,
+    vendor: String,
+    memo: String,
+    id: String,
+    postings: Vec<Posting>
+}
+
+impl JournalTransaction {
+    /** read the first line of a transaction.
+     * Lines that start something else, such as a directive or a comment, are passed over.
+     */
+    fn from_header(line: &str, format: JournalFormat) -> Result<Option<Self>, CheckbookError> {
+        let Some((date_text, rest)) = line.split_once(char::is_whitespace) else {
+            return Ok(None);
+        };
+
+        // ledger allows a second date after an equals sign, which the register has no place for.
+        let date_text = date_text.split('=').next().unwrap_or_default();
+
+        if !date_text.starts_with(|character: char| character.is_ascii_digit()) {
+            return Ok(None);
+        }
+
+        let date = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"].iter().find_map(|date_format| NaiveDate::parse_from_str(date_text, date_format).ok()).ok_or(CheckbookError::Parse(format!("{} is not a valid date.", date_text)))?;
+        let rest = rest.trim();
+
+        let mut transaction = Self {
+            date,
+            status: Status::Uncleared,
+            check_number: 0,
+            vendor: String::new(),
+            memo: String::new(),
+            id: String::new(),
+            postings: vec![]
+        };
+
+        if let JournalFormat::Beancount = format {
+            let (flag, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+            transaction.status = match flag {
+                "*" | "txn" => Status::Reconciled,
+                "!" => Status::Uncleared,
+                _ => return Ok(None)
+            };
+
+            let strings = quoted_strings(text);
+
+            (transaction.vendor, transaction.memo) = match strings.as_slice() {
+                [narration] => (narration.clone(), String::new()),
+                [payee, narration, ..] => (payee.clone(), narration.clone()),
+                [] => (String::new(), String::new())
+            };
+        } else {
+            let (description, comment) = rest.split_once(';').unwrap_or((rest, ""));
+            let mut description = description.trim();
+
+            if let Some(flagged) = description.strip_prefix('*') {
+                transaction.status = Status::Reconciled;
+                description = flagged.trim_start();
+            } else if let Some(flagged) = description.strip_prefix('!') {
+                transaction.status = Status::Cleared;
+                description = flagged.trim_start();
+            }
+
+            if let Some((code, after_code)) = description.strip_prefix('(').and_then(|coded| coded.split_once(')')) {
+                transaction.check_number = code.trim().parse::<u32>().unwrap_or(0);
+                description = after_code.trim_start();
+            }
+
+            transaction.vendor = description.to_string();
+            transaction.memo = comment.trim().to_string();
+        }
+
+        Ok(Some(transaction))
+    }
+
+    /// read a line of a transaction after the first, which is either a posting, metadata, or a comment.
+    fn add_line(&mut self, line: &str, format: JournalFormat) -> Result<(), CheckbookError> {
+        if let JournalFormat::Beancount = format {
+            if line.starts_with(';') {
+                return Ok(());
+            }
+
+            if let Some((key, value)) = metadata(line) {
+                let value = quoted_strings(value).first().cloned().unwrap_or(value.trim().to_string());
+
+                match (key, self.postings.last_mut()) {
+                    ("account" | "category", Some(posting)) => posting.name = Some(value),
+                    ("memo", Some(posting)) => posting.memo = value,
+                    ("id", None) => self.id = value,
+                    ("check", None) => self.check_number = value.parse::<u32>().unwrap_or(0),
+                    ("status", None) if value == "cleared" => self.status = Status::Cleared,
+                    _ => ()
+                }
+
+                return Ok(());
+            }
+        } else if let Some(comment) = line.strip_prefix(';') {
+            if let Some(("id", value)) = metadata(comment.trim()) {
+                self.id = value.trim().to_string();
+            }
+
+            return Ok(());
+        }
+
+        let (text, memo) = if let JournalFormat::Beancount = format {
+            (line.split(';').next().unwrap_or_default(), "")
+        } else {
+            line.split_once(';').unwrap_or((line, ""))
+        };
+
+        // postings can be flagged on their own, which the register has no place for.
+        let text = text.trim_start_matches(['*', '!']).trim();
+
+        let (account, amount_text) = if let JournalFormat::Beancount = format {
+            text.split_once(char::is_whitespace).unwrap_or((text, ""))
+        } else {
+            text.split_once("  ").or(text.split_once('\t')).unwrap_or((text, ""))
+        };
+
+        self.postings.push(Posting {
+            account: account.trim().to_string(),
+            amount: parse_amount(amount_text)?,
+            name: None,
+            memo: memo.trim().to_string()
+        });
+
+        Ok(())
+    }
+
+    fn entry(self) -> Result<JournalEntry, CheckbookError> {
+        let mut postings = self.postings;
+        let missing_amounts = postings.iter().filter(|posting| posting.amount.is_none()).count();
+
+        if missing_amounts > 1 {
+            return Err(CheckbookError::Parse("only one posting can leave out its amount.".to_string()));
+        }
+
+        let total = postings.iter().filter_map(|posting| posting.amount).fold(D64::ZERO, |sum, amount| sum + amount);
+
+        for posting in postings.iter_mut().filter(|posting| posting.amount.is_none()) {
+            posting.amount = Some(-total);
+        }
+
+        let Some(register_index) = postings.iter().position(|posting| matches!(root(&posting.account), "Assets" | "Liabilities")) else {
+            return Err(CheckbookError::Parse("there is no posting to an asset or liability account.".to_string()));
+        };
+
+        let register_posting = postings.remove(register_index);
+        let amount = register_posting.amount.unwrap_or(D64::ZERO);
+
+        let category = |posting: &Posting| -> Option<String> {
+            posting.name.clone().or(match posting.account.split_once(':') {
+                Some(("Expenses" | "Income", path)) => Some(path.to_string()),
+                _ => None
+            }).map(|category| normalized_path(&category)).filter(|category| !category.is_empty() && category != UNCATEGORIZED)
+        };
+
+        let splits: Vec<Split> = if postings.len() > 1 {
+            postings.iter().map(|posting| Split::from(&category(posting).unwrap_or_default(), -posting.amount.unwrap_or(D64::ZERO), &posting.memo)).collect()
+        } else {
+            vec![]
+        };
+
+        let mut transaction = Transaction::builder()
+        .set_date(&self.date.format("%Y-%m-%d").to_string())
+        .set_check_number(self.check_number)
+        .set_category(&if splits.is_empty() { postings.first().and_then(category).unwrap_or_default() } else { String::new() })
+        .set_vendor(&self.vendor)
+        .set_memo(&self.memo)
+        .set_is_reconciled(self.status == Status::Reconciled)
+        .build();
+
+        set_signed_amount(&mut transaction, amount);
+
+        Ok(JournalEntry {
+            record: Record::from(&self.id, transaction),
+            status: self.status,
+            splits,
+            account: register_posting.name.or(register_posting.account.split_once(':').map(|(_, name)| name.to_string())).unwrap_or_default(),
+            is_transfer: postings.iter().any(|posting| posting.account == TRANSFERS_ACCOUNT)
+        })
+    }
+}
+
+/// retrieve the account an amount is posted to for the given category, where negative amounts are expenses.
+fn category_account(category: &Option<String>, amount: D64) -> String {
+    let root = if amount > D64::ZERO { "Income" } else { "Expenses" };
+
+    format!("{}:{}", root, category.as_deref().unwrap_or(UNCATEGORIZED))
+}
+
+fn root(account: &str) -> &str {
+    account.split(':').next().unwrap_or_default()
+}
+
+/// retrieve an account name ledger can read, where two spaces in a row would end the name.
+fn ledger_account(account: &str) -> String {
+    account.split(':').map(|component| component.split_whitespace().collect::<Vec<&str>>().join(" ")).collect::<Vec<String>>().join(":")
+}
+
+/// retrieve an account name beancount can read, where each part starts with a capital letter or number and only has letters, numbers, and dashes.
+fn beancount_account(account: &str) -> String {
+    account.split(':').map(|component| {
+        let name: String = component.trim().chars().map(|character| if character.is_alphanumeric() || character == '-' { character } else { '-' }).collect();
+        let mut characters = name.chars();
+
+        match characters.next() {
+            Some(first) if first.is_alphanumeric() => first.to_uppercase().chain(characters).collect(),
+            _ => format!("X{}", name)
+        }
+    }).collect::<Vec<String>>().join(":")
+}
+
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// retrieve each string in double quotes, with backslash escapes undone.
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let mut current: Option<String> = None;
+    let mut characters = text.chars();
+
+    while let Some(character) = characters.next() {
+        match (character, current.as_mut()) {
+            ('"', None) => current = Some(String::new()),
+            ('"', Some(_)) => strings.extend(current.take()),
+            ('\\', Some(string)) => string.extend(characters.next()),
+            (character, Some(string)) => string.push(character),
+            _ => ()
+        }
+    }
+
+    strings
+}
+
+/// retrieve the key and value of a metadata line, such as id: "ABC", where keys start with a lowercase letter.
+fn metadata(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+
+    if key.starts_with(|character: char| character.is_ascii_lowercase()) && key.chars().all(|character| character.is_ascii_alphanumeric() || character == '-' || character == '_') {
+        Some((key, value))
+    } else {
+        None
+    }
+}
+
+/** parse the amount of a posting, such as $-42.50, -$42.50, or -42.50 USD.
+ * Anything after a price or balance assertion is left out, and a posting without an amount has none.
+ */
+fn parse_amount(text: &str) -> Result<Option<D64>, CheckbookError> {
+    let amount_text = text.split(['@', '=', '{']).next().unwrap_or_default().trim();
+    let digits: String = amount_text.chars().filter(|character| character.is_ascii_digit() || matches!(character, '.' | '-')).collect();
+
+    if digits.is_empty() {
+        return Ok(None);
+    }
+
+    digits.parse::<D64>().map(Some).map_err(|_| CheckbookError::Parse(format!("{} is not a valid amount.", amount_text)))
+}
@@ -0,0 +1,21 @@
+use bcheck::Record;
+use crate::status::Status;
+use fastnum::D64;
+
+/// Represents a record in the ledger, along with the running balance and status at that record.
+#[derive(Clone)]
+pub struct LedgerEntry {
+    pub record: Record,
+    pub balance: D64,
+    pub status: Status
+}
+
+impl LedgerEntry {
+    pub fn from(record: Record, balance: D64, status: Status) -> Self {
+        Self {
+            record,
+            balance,
+            status
+        }
+    }
+}
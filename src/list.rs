@@ -1,11 +1,14 @@
 use clap::Parser;
 use bcheck::{Record, TransactionType};
+use chrono::{ Local, Months, NaiveDate };
 use crate::records::Records;
-use crate::database::*;
+use crate::{ amount::signed_amount, database::*, errors::CheckbookError, ledger_entry::LedgerEntry, shared::parse_date, status::* };
+use fastnum::D64;
+use rusqlite::Connection;
 
 
 #[derive(Parser)]
-#[clap(version = "0.5.1", author = "Bryce Campbell", long_about = "Display the ledger. \r\n\r\nTo display the ledger, you can do something as simple as this: \r\n\r\nrcheckbook list \r\n\r\nWith the exception of a balance column, this will display the same data as if you did a TSV export and display everything. \r\n\r\nYou can also get filtered results with the c flag like this: \r\n\r\nrcheckbook list -c Utilities \r\n\r\nThis will list everything in the Utilities category. \r\n\r\nYou can filter by vendor and memo in the same capacity by using the -v and -m flags instead of or in addition to -c. \r\n\r\n-r and --unreconciled will filter out reconciled or unreconciled transaction, to see what you have reconciled or left to be accounted for.\r\n\r\nFinally, you can filter based on certain types of transactions by using -t like ths:\r\n\r\nrcheckbook list -t deposit\r\n\r\nThis will list only the transactions that can be considered income.\r\n\r\nTo get expenses, use withdrawal instead of deposit.\r\n\r\nIf you leave off -t, it will show everything.")]
+#[clap(version = "0.5.1", author = "Bryce Campbell", long_about = "Display the ledger. \r\n\r\nTo display the ledger, you can do something as simple as this: \r\n\r\nrcheckbook list \r\n\r\nWith the exception of a balance column, this will display the same data as if you did a TSV export and display everything. \r\n\r\nYou can also get filtered results with the c flag like this: \r\n\r\nrcheckbook list -c Utilities \r\n\r\nThis will list everything in the Utilities category, including categories nested under it, like Utilities:Electric. \r\n\r\nYou can filter by vendor and memo in the same capacity by using the -v and -m flags instead of or in addition to -c. \r\n\r\n-r and --not-reconciled will filter out reconciled or unreconciled transaction, to see what you have reconciled or left to be accounted for.\r\n\r\nEntries can also be cleared, meaning the bank has them but they have not been reconciled yet. These are marked with a C, and -s can be used to only see entries that are uncleared, cleared, or reconciled.\r\n\r\nFinally, you can filter based on certain types of transactions by using -t like ths:\r\n\r\nrcheckbook list -t deposit\r\n\r\nThis will list only the transactions that can be considered income.\r\n\r\nTo get expenses, use withdrawal instead of deposit.\r\n\r\nIf you leave off -t, it will show everything.\r\n\r\nTo only see a single account, use --account. Balances will then be for that account alone, instead of every account combined.\r\n\r\nEntries that are split across categories are followed by one indented line per split, showing the category, memo, and amount.\r\n\r\nTo see scheduled entries that have not been posted yet, use --upcoming. These run through a month from today, unless another date is given with --through, and their balances carry on from the end of the register.")]
 pub struct List {
 
     #[clap(default_value = "~/.checkbook/register.db")]
@@ -26,54 +29,79 @@ pub struct List {
     #[clap(long)]
     pub not_reconciled: bool,
 
+    #[clap(long, short, value_enum, conflicts_with_all = ["reconciled", "not_reconciled"])]
+    pub status: Option<Status>,
+
     #[clap(long, short)]
-    pub transaction_type: Option<TransactionType>
+    pub transaction_type: Option<TransactionType>,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long)]
+    pub upcoming: bool,
+
+    #[clap(long, requires = "upcoming")]
+    pub through: Option<String>
 }
 
 impl List {
-    pub fn run(&self) {
-        copy_database_if_not_exists(&self.file_path);
-        let record_store = Records::from(load_records_from_db(&self.file_path));
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        let record_store = Records::from(load_ledger_from_db(&db, &self.account)?);
+
+        let statuses = statuses_to_show(&self.status, self.reconciled, self.not_reconciled);
 
         if self.reconciled && self.not_reconciled {
-            print!("Please use only one flag!\r\nNo transactions can be both reconciled and unreconciled.\r\n")
+            Err(CheckbookError::InvalidInput("Please use only one flag!\r\nNo transactions can be both reconciled and unreconciled.".to_string()))
+        } else if self.upcoming {
+            let through = match &self.through {
+                Some(through) => parse_date(through)?,
+                None => Local::now().date_naive() + Months::new(1)
+            };
+
+            let projected_store = Records::from(projected_entries(&db, &record_store, &self.account, through)?);
+
+            display(&projected_store, &self.category, &self.vendor, &self.memo, &statuses, &self.transaction_type, &db)
         } else {
-            display(&record_store, &self.category, &self.vendor, &self.memo, &self.reconciled, &self.not_reconciled, &self.transaction_type, &self.file_path)
+            display(&record_store, &self.category, &self.vendor, &self.memo, &statuses, &self.transaction_type, &db)
         }  
     }
 }
 
-fn retrieve_records(r: &Vec<Record>, category: &Option<String>, vendor: &Option<String>, memo: &Option<String>, reconciled: &bool, unreconciled: &bool, transaction_type: &Option<TransactionType>) -> Vec<Record> {
-    let mut filtered_records: Vec<Record> = r.clone();
-    if let Some(category) = category {
-        filtered_records = filtered_records.into_iter().filter(|record| record.transaction.category.clone().unwrap_or("Uncategorized".to_string()).to_lowercase() == category.to_string().to_lowercase() || record.transaction.category.clone().unwrap_or("Uncategorized".to_string()).to_lowercase().contains(&category.to_string().to_lowercase())).collect();
-    }
+/** retrieve every scheduled entry that has not been posted through the given date,
+ * with balances carried on from the end of the register.
+ */
+fn projected_entries(db: &Connection, store: &Records, account: &Option<String>, through: NaiveDate) -> Result<Vec<LedgerEntry>, CheckbookError> {
+    let mut balance = store.sorted_records().last().map(|record| store.balance_for_record(record)).unwrap_or(D64::ZERO);
 
-    if let Some(vendor) = vendor {
-        filtered_records = filtered_records.into_iter().filter(|record| record.transaction.vendor.to_lowercase() == vendor.to_lowercase() || record.transaction.vendor.to_lowercase().contains(&vendor.to_string().to_lowercase())).collect();
-    }
+    let mut projected_records: Vec<Record> = load_schedules_from_db(db)?.into_iter()
+    .filter(|schedule| account.as_ref().is_none_or(|account| schedule.account.as_ref().is_some_and(|name| name.to_lowercase() == account.to_lowercase())))
+    .flat_map(|schedule| schedule.due_dates(through).into_iter().map(|date| schedule.record(&format!("SCHEDULE-{}-{}", schedule.id, date.format("%Y-%m-%d")), date)).collect::<Vec<Record>>())
+    .collect();
 
-    if let Some(memo) = memo {
-        filtered_records = filtered_records.into_iter().filter(|record| record.transaction.memo.to_lowercase() == memo.to_lowercase() || record.transaction.memo.to_lowercase().contains(&memo.to_string().to_lowercase())).collect();
-    }
+    projected_records.sort_by_key(|record| record.transaction.date);
 
-    if reconciled.to_owned() {
-        filtered_records = filtered_records.into_iter().filter(|record| record.transaction.is_reconciled).collect();
-    } else if unreconciled.to_owned() {
-        filtered_records = filtered_records.into_iter().filter(|record| !record.transaction.is_reconciled).collect();
-    }
+    Ok(projected_records.into_iter().map(|record| {
+        balance += signed_amount(&record.transaction);
+        LedgerEntry::from(record, balance, Status::Uncleared)
+    }).collect())
+}
 
-    if let Some(record_type) = transaction_type {
-        filtered_records = filtered_records.into_iter().filter(|record| record.transaction.transaction_type == record_type.to_owned()).collect();
-    }
+fn display(store: &Records, category: &Option<String>, vendor: &Option<String>, memo: &Option<String>, statuses: &[Status], transaction_type: &Option<TransactionType>, db: &Connection) -> Result<(), CheckbookError> {
+    let splits = load_splits_from_db(db)?;
 
-    return filtered_records;
-}
+    for record in store.filtered_records(&splits, category, vendor, memo, statuses, transaction_type) {
+        let balance = store.balance_for_record(&record);
+        println!("{}\t{:.2}", record_with_status(&record, &store.status_for_record(&record)), balance);
 
-fn display(store: &Records, category: &Option<String>, vendor: &Option<String>, memo: &Option<String>, reconciled: &bool, unreconciled: &bool, transaction_type: &Option<TransactionType>, db: &str) {
-    for record in retrieve_records(&store.sorted_records(), category, vendor, memo, reconciled, unreconciled, transaction_type) {
-        let balance = store.balance_for_record(db, &record);
-        println!("{}\t{:.2}", record, balance);
+        if let Some(record_splits) = splits.get(&record.id.to_lowercase()) {
+            for split in record_splits {
+                println!("\t{}", split);
+            }
+        }
     }
+
+    Ok(())
 }
 
@@ -13,15 +13,48 @@ mod shared;
 mod summary;
 mod period;
 mod date_range;
+mod account;
+mod account_details;
+mod transfer;
+mod split;
+mod amount;
+mod migrations;
+mod db;
+mod ledger_entry;
+mod category;
+mod category_details;
+mod category_path;
+mod rule;
+mod rules;
+mod recurrence;
+mod scheduled_entry;
+mod schedule;
+mod forecast_source;
+mod forecast;
+mod monthly_budget;
+mod budget;
+mod reconciliation;
+mod reconcile;
+mod status;
+mod sign_convention;
+mod csv_profile;
+mod csv_profiles;
+mod ofx;
+mod journal;
+mod export_column;
+mod delimited_layout;
+mod conflict_policy;
+mod record_comparison;
 
 use rcheckbook::RCheckbook;
 use clap::Parser;
 use subcommand::SubCommand;
+use std::process;
 
 fn main() {
     let checkbook: RCheckbook = RCheckbook::parse();
 
-    match checkbook.subcommand {
+    let result = match checkbook.subcommand {
         SubCommand::Add(a) => a.run(),
         SubCommand::Import(i) => i.run(),
         SubCommand::Export(e) => e.run(),
@@ -29,5 +62,20 @@ fn main() {
         SubCommand::Remove(r) => r.run(),
         SubCommand::Update(u) => u.run(),
         SubCommand::Summary(s) => s.run(),
+        SubCommand::Forecast(f) => f.run(),
+        SubCommand::Budget(b) => b.run(),
+        SubCommand::Reconcile(r) => r.run(),
+        SubCommand::Account(a) => a.run(),
+        SubCommand::Transfer(t) => t.run(),
+        SubCommand::Category(c) => c.run(),
+        SubCommand::Rules(r) => r.run(),
+        SubCommand::Schedule(s) => s.run(),
+        SubCommand::CsvProfiles(c) => c.run(),
+        SubCommand::Db(d) => d.run(),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(error.exit_code());
     }
 }
\ No newline at end of file
@@ -0,0 +1,373 @@
+/** Represents a change to the schema, which brings a database up to the given version.
+ * The statements of a migration are run in a single transaction,
+ * along with recording the new version.
+ */
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub statements: &'static [&'static str]
+}
+
+/** every migration, in the order they are applied.
+ * Version 0 is the schema from before databases were versioned.
+ * Migrations that have shipped must never be changed, only added to.
+ */
+pub const MIGRATIONS: [Migration; 13] = [
+    Migration {
+        version: 1,
+        description: "add accounts",
+        statements: &[ACCOUNTS, LEDGER_VIEW]
+    },
+    Migration {
+        version: 2,
+        description: "link transfers between accounts",
+        statements: &[TRANSFERS]
+    },
+    Migration {
+        version: 3,
+        description: "add splits",
+        statements: &[SPLITS]
+    },
+    Migration {
+        version: 4,
+        description: "store amounts as whole cents",
+        statements: &[TRADE_AMOUNTS, SPLIT_AMOUNTS, LEDGER_VIEW]
+    },
+    Migration {
+        version: 5,
+        description: "order entries within a day",
+        statements: &[SEQUENCES, SEQUENCED_LEDGER_VIEW]
+    },
+    Migration {
+        version: 6,
+        description: "nest categories",
+        statements: &[CATEGORY_PARENTS]
+    },
+    Migration {
+        version: 7,
+        description: "add rules",
+        statements: &[RULES]
+    },
+    Migration {
+        version: 8,
+        description: "add schedules",
+        statements: &[SCHEDULES]
+    },
+    Migration {
+        version: 9,
+        description: "add budgets",
+        statements: &[BUDGETS]
+    },
+    Migration {
+        version: 10,
+        description: "record reconciliations",
+        statements: &[RECONCILIATIONS]
+    },
+    Migration {
+        version: 11,
+        description: "track cleared entries",
+        statements: &[TRADE_STATUSES, STATUS_LEDGER_VIEW]
+    },
+    Migration {
+        version: 12,
+        description: "add csv profiles",
+        statements: &[CSV_PROFILES]
+    },
+    Migration {
+        version: 13,
+        description: "remember bank transaction identifiers",
+        statements: &[FITIDS]
+    }
+];
+
+/// retrieve the version of the schema that new databases are created with.
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|migration| migration.version).unwrap_or(0)
+}
+
+const ACCOUNTS: &str = r#"CREATE TABLE "accounts" (
+	"id"	INTEGER,
+	"name"	TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	"closed"	INTEGER NOT NULL DEFAULT 0 CHECK("closed" IN (0, 1)),
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+INSERT INTO "accounts" VALUES (1,'Default',0);
+ALTER TABLE "trades" ADD COLUMN "account" INTEGER NOT NULL DEFAULT 1 REFERENCES "accounts"("id");"#;
+
+const TRANSFERS: &str = r#"ALTER TABLE "trades" ADD COLUMN "transfer" TEXT DEFAULT NULL;"#;
+
+const SPLITS: &str = r#"CREATE TABLE "splits" (
+	"id"	INTEGER,
+	"trade"	TEXT NOT NULL REFERENCES "trades"("id"),
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"amount"	REAL NOT NULL DEFAULT 0.0,
+	"memo"	TEXT DEFAULT '',
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;"#;
+
+/** amounts used to be stored as floating point numbers, but are now whole numbers of cents.
+ * Since the tables are strict, they have to be rebuilt to change the type of the column.
+ */
+const TRADE_AMOUNTS: &str = r#"DROP VIEW IF EXISTS "ledger";
+CREATE TABLE "trades_upgrade" (
+	"id"	TEXT,
+	"date"	TEXT NOT NULL DEFAULT (DATE('now')),
+	"check_number"	INTEGER DEFAULT NULL,
+	"vendor"	TEXT DEFAULT '',
+	"memo"	TEXT DEFAULT '',
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	"category"	INTEGER DEFAULT NULL,
+	"reconciled"	INTEGER NOT NULL DEFAULT 0 CHECK("reconciled" IN (0, 1)),
+	"account"	INTEGER NOT NULL DEFAULT 1 REFERENCES "accounts"("id"),
+	"transfer"	TEXT DEFAULT NULL,
+	PRIMARY KEY("id")
+) STRICT;
+INSERT INTO "trades_upgrade"("id", "date", "check_number", "vendor", "memo", "amount", "category", "reconciled", "account", "transfer")
+SELECT "id", "date", "check_number", "vendor", "memo", CAST(ROUND("amount" * 100) AS INTEGER), "category", "reconciled", "account", "transfer" FROM "trades";
+DROP TABLE "trades";
+ALTER TABLE "trades_upgrade" RENAME TO "trades";"#;
+
+const SPLIT_AMOUNTS: &str = r#"CREATE TABLE "splits_upgrade" (
+	"id"	INTEGER,
+	"trade"	TEXT NOT NULL REFERENCES "trades"("id"),
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	"memo"	TEXT DEFAULT '',
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;
+INSERT INTO "splits_upgrade"("id", "trade", "category", "amount", "memo")
+SELECT "id", "trade", "category", CAST(ROUND("amount" * 100) AS INTEGER), "memo" FROM "splits";
+DROP TABLE "splits";
+ALTER TABLE "splits_upgrade" RENAME TO "splits";"#;
+
+/** entries on the same day used to come out in no particular order.
+ * Existing entries keep the order they were stored in.
+ */
+const SEQUENCES: &str = r#"ALTER TABLE "trades" ADD COLUMN "sequence" INTEGER NOT NULL DEFAULT 0;
+UPDATE "trades" SET "sequence" = "rowid";
+CREATE INDEX "trades_sequence" ON "trades"("sequence");"#;
+
+/** categories are stored with their full path, such as Utilities:Electric, and point at their parent.
+ * Paths that were imported before categories could be nested get any missing parents added.
+ */
+const CATEGORY_PARENTS: &str = r#"ALTER TABLE "categories" ADD COLUMN "parent" INTEGER DEFAULT NULL REFERENCES "categories"("id");
+INSERT INTO "categories"("category")
+WITH RECURSIVE prefixes(path, rest) AS (
+	SELECT '', category || ':' FROM categories WHERE INSTR(category, ':') > 0
+	UNION
+	SELECT CASE WHEN path = '' THEN '' ELSE path || ':' END || SUBSTR(rest, 1, INSTR(rest, ':') - 1), SUBSTR(rest, INSTR(rest, ':') + 1)
+	FROM prefixes
+	WHERE rest <> ''
+)
+SELECT DISTINCT path FROM prefixes p
+WHERE path <> ''
+AND NOT EXISTS (SELECT 1 FROM categories c WHERE c.category = p.path COLLATE NOCASE);
+UPDATE "categories" SET "parent" = (
+	SELECT p.id FROM categories p
+	WHERE SUBSTR(categories.category, 1, LENGTH(p.category) + 1) = p.category || ':' COLLATE NOCASE
+	AND INSTR(SUBSTR(categories.category, LENGTH(p.category) + 2), ':') = 0
+);"#;
+
+/** rules match entries on their vendor, memo, amount, and type, then fill in their category, vendor, or memo.
+ * Amounts are stored as whole cents, like entries.
+ */
+const RULES: &str = r#"CREATE TABLE "rules" (
+	"id"	INTEGER,
+	"vendor_pattern"	TEXT DEFAULT NULL,
+	"memo_pattern"	TEXT DEFAULT NULL,
+	"regex"	INTEGER NOT NULL DEFAULT 0 CHECK("regex" IN (0, 1)),
+	"minimum"	INTEGER DEFAULT NULL,
+	"maximum"	INTEGER DEFAULT NULL,
+	"transaction_type"	TEXT DEFAULT NULL CHECK("transaction_type" IN ('deposit', 'withdrawal')),
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"vendor"	TEXT DEFAULT NULL,
+	"memo"	TEXT DEFAULT NULL,
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;"#;
+
+/** schedules are templates for entries that come around regularly.
+ * The date of the last occurrence that was posted keeps posting from adding the same entry twice.
+ */
+const SCHEDULES: &str = r#"CREATE TABLE "schedules" (
+	"id"	INTEGER,
+	"vendor"	TEXT NOT NULL,
+	"memo"	TEXT DEFAULT '',
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	"category"	INTEGER DEFAULT NULL REFERENCES "categories"("id"),
+	"account"	INTEGER NOT NULL DEFAULT 1 REFERENCES "accounts"("id"),
+	"recurrence"	TEXT NOT NULL CHECK("recurrence" IN ('weekly', 'biweekly', 'monthly', 'last-business-day')),
+	"day"	INTEGER DEFAULT NULL CHECK("day" BETWEEN 1 AND 31),
+	"start_date"	TEXT NOT NULL DEFAULT (DATE('now')),
+	"last_posted"	TEXT DEFAULT NULL,
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;"#;
+
+/** budgets are the amount planned to be spent in a category each month.
+ * The period is the month a budget covers, such as 2026-10.
+ */
+const BUDGETS: &str = r#"CREATE TABLE "budgets" (
+	"id"	INTEGER,
+	"category"	INTEGER NOT NULL REFERENCES "categories"("id"),
+	"period"	TEXT NOT NULL,
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY("id" AUTOINCREMENT),
+	UNIQUE("category", "period")
+) STRICT;"#;
+
+/// every completed reconciliation, where a missing account means every account was reconciled together.
+const RECONCILIATIONS: &str = r#"CREATE TABLE "reconciliations" (
+	"id"	INTEGER,
+	"account"	INTEGER DEFAULT NULL REFERENCES "accounts"("id"),
+	"statement_date"	TEXT NOT NULL,
+	"statement_balance"	INTEGER NOT NULL DEFAULT 0,
+	"reconciled_on"	TEXT NOT NULL DEFAULT (DATE('now')),
+	"entries"	INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;"#;
+
+/** saved layouts of the CSV files banks export.
+ * Columns are kept as a mapping, such as date=1,payee=3,amount=5.
+ */
+const CSV_PROFILES: &str = r#"CREATE TABLE "csv_profiles" (
+	"id"	INTEGER,
+	"name"	TEXT NOT NULL UNIQUE COLLATE NOCASE,
+	"columns"	TEXT NOT NULL,
+	"date_format"	TEXT NOT NULL DEFAULT '%Y-%m-%d',
+	"delimiter"	TEXT NOT NULL DEFAULT ',',
+	"skip_rows"	INTEGER NOT NULL DEFAULT 0,
+	"sign"	TEXT NOT NULL DEFAULT 'deposits-positive' CHECK("sign" IN ('deposits-positive', 'withdrawals-positive')),
+	"decimal_separator"	TEXT NOT NULL DEFAULT '.' CHECK("decimal_separator" IN ('.', ',')),
+	PRIMARY KEY("id" AUTOINCREMENT)
+) STRICT;"#;
+
+/// the identifier a bank gives an entry in OFX files, so that importing the same file twice adds nothing new.
+const FITIDS: &str = r#"ALTER TABLE "trades" ADD COLUMN "fitid" TEXT DEFAULT NULL;
+CREATE INDEX "trades_fitid" ON "trades"("account", "fitid");"#;
+
+const LEDGER_VIEW: &str = r#"DROP VIEW IF EXISTS "ledger";
+CREATE VIEW ledger AS
+SELECT t.id AS 'id',
+DATE(t.date) AS 'date',
+t.check_number,
+CASE t.reconciled
+	WHEN 1
+		THEN 'Y'
+	ELSE 'N'
+END AS 'reconciled',
+t.vendor,
+t.memo,
+c.category,
+t.amount,
+SUM(t.amount) OVER (
+	PARTITION BY t.account
+	ORDER BY DATE(t.date) ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'balance',
+a.name AS 'account',
+SUM(t.amount) OVER (
+	ORDER BY DATE(t.date) ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'total_balance'
+FROM trades t
+LEFT JOIN categories c
+ON t.category = c.id
+LEFT JOIN accounts a
+ON t.account = a.id
+ORDER BY DATE(t.date) ASC;"#;
+
+
+/// the ledger view, with entries on the same day kept in order.
+const SEQUENCED_LEDGER_VIEW: &str = r#"DROP VIEW IF EXISTS "ledger";
+CREATE VIEW ledger AS
+SELECT t.id AS 'id',
+DATE(t.date) AS 'date',
+t.check_number,
+CASE t.reconciled
+	WHEN 1
+		THEN 'Y'
+	ELSE 'N'
+END AS 'reconciled',
+t.vendor,
+t.memo,
+c.category,
+t.amount,
+SUM(t.amount) OVER (
+	PARTITION BY t.account
+	ORDER BY DATE(t.date) ASC, t.sequence ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'balance',
+a.name AS 'account',
+SUM(t.amount) OVER (
+	ORDER BY DATE(t.date) ASC, t.sequence ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'total_balance'
+FROM trades t
+LEFT JOIN categories c
+ON t.category = c.id
+LEFT JOIN accounts a
+ON t.account = a.id
+ORDER BY DATE(t.date) ASC, t.sequence ASC;"#;
+
+/** entries used to be either reconciled or not, but can now be cleared in between.
+ * Since the tables are strict, trades has to be rebuilt to replace the reconciled column with a status.
+ */
+const TRADE_STATUSES: &str = r#"DROP VIEW IF EXISTS "ledger";
+CREATE TABLE "trades_upgrade" (
+	"id"	TEXT,
+	"date"	TEXT NOT NULL DEFAULT (DATE('now')),
+	"check_number"	INTEGER DEFAULT NULL,
+	"vendor"	TEXT DEFAULT '',
+	"memo"	TEXT DEFAULT '',
+	"amount"	INTEGER NOT NULL DEFAULT 0,
+	"category"	INTEGER DEFAULT NULL,
+	"status"	TEXT NOT NULL DEFAULT 'uncleared' CHECK("status" IN ('uncleared', 'cleared', 'reconciled')),
+	"account"	INTEGER NOT NULL DEFAULT 1 REFERENCES "accounts"("id"),
+	"transfer"	TEXT DEFAULT NULL,
+	"sequence"	INTEGER NOT NULL DEFAULT 0,
+	PRIMARY KEY("id")
+) STRICT;
+INSERT INTO "trades_upgrade"("id", "date", "check_number", "vendor", "memo", "amount", "category", "status", "account", "transfer", "sequence")
+SELECT "id", "date", "check_number", "vendor", "memo", "amount", "category", CASE "reconciled" WHEN 1 THEN 'reconciled' ELSE 'uncleared' END, "account", "transfer", "sequence" FROM "trades";
+DROP TABLE "trades";
+ALTER TABLE "trades_upgrade" RENAME TO "trades";
+CREATE INDEX "trades_sequence" ON "trades"("sequence");"#;
+
+/// the ledger view, with cleared entries marked with a C.
+const STATUS_LEDGER_VIEW: &str = r#"DROP VIEW IF EXISTS "ledger";
+CREATE VIEW ledger AS
+SELECT t.id AS 'id',
+DATE(t.date) AS 'date',
+t.check_number,
+CASE t.status
+	WHEN 'reconciled'
+		THEN 'Y'
+	WHEN 'cleared'
+		THEN 'C'
+	ELSE 'N'
+END AS 'reconciled',
+t.vendor,
+t.memo,
+c.category,
+t.amount,
+SUM(t.amount) OVER (
+	PARTITION BY t.account
+	ORDER BY DATE(t.date) ASC, t.sequence ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'balance',
+a.name AS 'account',
+SUM(t.amount) OVER (
+	ORDER BY DATE(t.date) ASC, t.sequence ASC
+	ROWS BETWEEN
+	UNBOUNDED PRECEDING
+	AND CURRENT ROW) AS 'total_balance'
+FROM trades t
+LEFT JOIN categories c
+ON t.category = c.id
+LEFT JOIN accounts a
+ON t.account = a.id
+ORDER BY DATE(t.date) ASC, t.sequence ASC;"#;
\ No newline at end of file
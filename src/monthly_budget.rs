@@ -0,0 +1,24 @@
+use chrono::{ Datelike, NaiveDate };
+use fastnum::D64;
+
+/// Represents the amount planned to be spent in a category over a single month.
+pub struct MonthlyBudget {
+    pub category: String,
+    pub month: NaiveDate,
+    pub amount: D64
+}
+
+impl MonthlyBudget {
+    pub fn from(category: &str, month: NaiveDate, amount: D64) -> Self {
+        Self {
+            category: category.to_string(),
+            month,
+            amount
+        }
+    }
+
+    /// check if the budget covers the month that the given date is in.
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        date.year() == self.month.year() && date.month() == self.month.month()
+    }
+}
@@ -0,0 +1,390 @@
+use bcheck::{ Record, Transaction, TransactionType };
+use chrono::{ Local, NaiveDate };
+use clap::ValueEnum;
+use fastnum::D64;
+use crate::{ amount::*, errors::CheckbookError };
+
+/// the longest vendor OFX allows, in characters.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// the longest account identifier OFX allows, in characters.
+const MAX_ACCOUNT_LENGTH: usize = 22;
+
+/// the currency of exported statements, since the register keeps amounts without one.
+const CURRENCY: &str = "USD";
+
+/// the headers of an OFX 2.2 file, which is XML.
+const HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\r\n<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>\r\n";
+
+/// the status of a response that succeeded, which every response in an OFX file needs.
+const SUCCESS_STATUS: &str = "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>";
+
+/// Represents the kinds of bank accounts an OFX statement can be for.
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum OfxAccountType {
+    Checking,
+    Savings,
+    MoneyMarket,
+    CreditLine
+}
+
+impl OfxAccountType {
+    /// retrieve the name OFX gives the account type.
+    fn ofx_name(&self) -> &'static str {
+        match self {
+            Self::Checking => "CHECKING",
+            Self::Savings => "SAVINGS",
+            Self::MoneyMarket => "MONEYMRKT",
+            Self::CreditLine => "CREDITLINE"
+        }
+    }
+}
+
+/** Represents the account an exported statement is for.
+ * Banks are identified by their routing number,
+ * which finance tools check before loading a statement.
+ */
+pub struct OfxAccount {
+    pub bank_id: String,
+    pub account_id: String,
+    pub account_type: OfxAccountType
+}
+
+/** Represents a transaction in an OFX statement.
+ * Banks give each transaction a FITID that never changes,
+ * which is how transactions that were already imported are recognized.
+ */
+pub struct OfxTransaction {
+    pub fitid: String,
+    pub record: Record
+}
+
+/// Represents the balance a bank reports at the end of a statement, along with the date it is as of.
+pub struct LedgerBalance {
+    pub amount: D64,
+    pub date: NaiveDate
+}
+
+/// Represents a bank or credit card statement in an OFX file.
+pub struct OfxStatement {
+    pub transactions: Vec<OfxTransaction>,
+    pub ledger_balance: Option<LedgerBalance>
+}
+
+impl OfxStatement {
+    /** retrieve every statement in the given content, along with the transactions that could not be read.
+     * OFX 1.x files are SGML, where elements are not closed, while OFX 2.x files are XML.
+     * Both close aggregates such as STMTTRN, so values are read up to the next tag either way.
+     */
+    pub fn parse(content: &str) -> Result<(Vec<Self>, Vec<CheckbookError>), CheckbookError> {
+        let mut statements = vec![];
+        let mut invalid_transactions = vec![];
+
+        for statement in aggregates(content, "STMTRS").into_iter().chain(aggregates(content, "CCSTMTRS")) {
+            let mut transactions = vec![];
+
+            for (index, transaction) in aggregates(statement, "STMTTRN").into_iter().enumerate() {
+                match transaction_from_aggregate(transaction) {
+                    Ok(transaction) => transactions.push(transaction),
+                    Err(error) => invalid_transactions.push(CheckbookError::Parse(format!("transaction {}: {}", index + 1, error)))
+                }
+            }
+
+            let ledger_balance = match aggregates(statement, "LEDGERBAL").first() {
+                Some(balance) => Some(LedgerBalance {
+                    amount: parse_amount(&required_value(balance, "BALAMT")?)?,
+                    date: parse_date(&required_value(balance, "DTASOF")?)?
+                }),
+                None => None
+            };
+
+            statements.push(Self { transactions, ledger_balance });
+        }
+
+        if statements.is_empty() {
+            return Err(CheckbookError::Parse("no bank or credit card statements could be found.".to_string()));
+        }
+
+        Ok((statements, invalid_transactions))
+    }
+
+    /** present the statement as an OFX 2.x bank statement response for the given account.
+     * Each record is identified by its FITID, which is the record's own identifier when exported,
+     * and the statement covers the dates of the first and last records.
+     */
+    pub fn to_ofx(&self, account: &OfxAccount) -> String {
+        let today = Local::now().date_naive();
+        let start = self.transactions.iter().map(|transaction| transaction.record.transaction.date).min().unwrap_or(today);
+        let end = self.transactions.iter().map(|transaction| transaction.record.transaction.date).max().unwrap_or(today);
+
+        let mut ofx = format!("{}<OFX>\r\n", HEADER);
+
+        let sign_on = format!("<SIGNONMSGSRSV1><SONRS>{}<DTSERVER>{}</DTSERVER><LANGUAGE>ENG</LANGUAGE></SONRS></SIGNONMSGSRSV1>\r\n", SUCCESS_STATUS, Local::now().format("%Y%m%d%H%M%S"));
+        ofx.push_str(&sign_on);
+
+        let response = format!("<BANKMSGSRSV1><STMTTRNRS><TRNUID>0</TRNUID>{}\r\n<STMTRS><CURDEF>{}</CURDEF>\r\n", SUCCESS_STATUS, CURRENCY);
+        ofx.push_str(&response);
+
+        let account_from = format!("<BANKACCTFROM><BANKID>{}</BANKID><ACCTID>{}</ACCTID><ACCTTYPE>{}</ACCTTYPE></BANKACCTFROM>\r\n", escape(&account.bank_id), escape(&truncated(&account.account_id, MAX_ACCOUNT_LENGTH)), account.account_type.ofx_name());
+        ofx.push_str(&account_from);
+
+        let transaction_list = format!("<BANKTRANLIST><DTSTART>{}</DTSTART><DTEND>{}</DTEND>\r\n", start.format("%Y%m%d"), end.format("%Y%m%d"));
+        ofx.push_str(&transaction_list);
+
+        for transaction in &self.transactions {
+            ofx.push_str(&transaction.to_ofx());
+        }
+
+        ofx.push_str("</BANKTRANLIST>\r\n");
+
+        if let Some(balance) = &self.ledger_balance {
+            let ledger_balance = format!("<LEDGERBAL><BALAMT>{:.2}</BALAMT><DTASOF>{}</DTASOF></LEDGERBAL>\r\n", balance.amount, balance.date.format("%Y%m%d"));
+            ofx.push_str(&ledger_balance);
+        }
+
+        ofx.push_str("</STMTRS></STMTTRNRS></BANKMSGSRSV1>\r\n</OFX>\r\n");
+
+        ofx
+    }
+}
+
+impl OfxTransaction {
+    /// present the transaction as a STMTTRN aggregate, with elements in the order OFX requires.
+    fn to_ofx(&self) -> String {
+        let transaction = &self.record.transaction;
+        let check_number = transaction.check_number.filter(|check_number| *check_number > 0);
+
+        let transaction_type = match (&transaction.transaction_type, check_number) {
+            (TransactionType::Deposit, _) => "CREDIT",
+            (TransactionType::Withdrawal, Some(_)) => "CHECK",
+            (TransactionType::Withdrawal, None) => "DEBIT"
+        };
+
+        let mut aggregate = format!("<STMTTRN><TRNTYPE>{}</TRNTYPE><DTPOSTED>{}</DTPOSTED><TRNAMT>{:.2}</TRNAMT><FITID>{}</FITID>", transaction_type, transaction.date.format("%Y%m%d"), signed_amount(transaction), escape(&self.fitid));
+
+        if let Some(check_number) = check_number {
+            let check = format!("<CHECKNUM>{}</CHECKNUM>", check_number);
+            aggregate.push_str(&check);
+        }
+
+        if !transaction.vendor.is_empty() {
+            let name = format!("<NAME>{}</NAME>", escape(&truncated(&transaction.vendor, MAX_NAME_LENGTH)));
+            aggregate.push_str(&name);
+        }
+
+        if !transaction.memo.is_empty() {
+            let memo = format!("<MEMO>{}</MEMO>", escape(&transaction.memo));
+            aggregate.push_str(&memo);
+        }
+
+        aggregate.push_str("</STMTTRN>\r\n");
+
+        aggregate
+    }
+}
+
+fn transaction_from_aggregate(aggregate: &str) -> Result<OfxTransaction, CheckbookError> {
+    let fitid = required_value(aggregate, "FITID")?;
+    let date = parse_date(&required_value(aggregate, "DTPOSTED")?)?;
+    let amount = parse_amount(&required_value(aggregate, "TRNAMT")?)?;
+    let memo = value(aggregate, "MEMO").unwrap_or_default();
+
+    // a few banks leave out the name and put the payee in the memo instead.
+    let vendor = value(aggregate, "NAME").unwrap_or(memo.clone());
+
+    let mut transaction = Transaction::builder()
+    .set_date(&date.format("%Y-%m-%d").to_string())
+    .set_check_number(value(aggregate, "CHECKNUM").and_then(|check_number| check_number.parse::<u32>().ok()).unwrap_or(0))
+    .set_vendor(&vendor)
+    .set_memo(if vendor == memo { "" } else { &memo })
+    .build();
+
+    set_signed_amount(&mut transaction, amount);
+
+    Ok(OfxTransaction { fitid, record: Record::from("", transaction) })
+}
+
+/// retrieve the content of each aggregate with the given name, such as every STMTTRN in a statement.
+fn aggregates<'a>(content: &'a str, name: &str) -> Vec<&'a str> {
+    let start_tag = format!("<{}>", name);
+    let end_tag = format!("</{}>", name);
+    let mut found = vec![];
+    let mut remaining = content;
+
+    while let Some(start) = remaining.find(&start_tag) {
+        let after_start = &remaining[start + start_tag.len()..];
+
+        let Some(end) = after_start.find(&end_tag) else {
+            break;
+        };
+
+        found.push(&after_start[..end]);
+        remaining = &after_start[end + end_tag.len()..];
+    }
+
+    found
+}
+
+/// retrieve the value of the first element with the given name, which runs until the next tag.
+fn value(content: &str, name: &str) -> Option<String> {
+    let start_tag = format!("<{}>", name);
+    let start = content.find(&start_tag)? + start_tag.len();
+    let text = &content[start..];
+    let end = text.find('<').unwrap_or(text.len());
+
+    let unescaped = text[..end].trim().replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&");
+
+    if unescaped.is_empty() { None } else { Some(unescaped) }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn truncated(text: &str, length: usize) -> String {
+    text.chars().take(length).collect()
+}
+
+fn required_value(content: &str, name: &str) -> Result<String, CheckbookError> {
+    value(content, name).ok_or(CheckbookError::Parse(format!("{} is missing.", name)))
+}
+
+/// parse a date such as 20261031 or 20261031120000.000[-5:EST], where only the day is kept.
+fn parse_date(text: &str) -> Result<NaiveDate, CheckbookError> {
+    text.get(..8).and_then(|day| NaiveDate::parse_from_str(day, "%Y%m%d").ok()).ok_or(CheckbookError::Parse(format!("{} is not a valid OFX date.", text)))
+}
+
+/// parse an amount, which some banks write with a comma in place of the decimal point.
+fn parse_amount(text: &str) -> Result<D64, CheckbookError> {
+    text.replace(',', ".").parse::<D64>().map_err(|_| CheckbookError::Parse(format!("{} is not a valid amount.", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the entries of a small register, as identifier, date, check number, vendor, memo, and signed amount.
+    const ENTRIES: [(&str, &str, u32, &str, &str, &str); 3] = [
+        ("6f1c2d1e-0001", "2026-10-01", 0, "ACME Payroll & Benefits", "October", "1500.00"),
+        ("6f1c2d1e-0002", "2026-10-03", 1042, "Hausverwaltung Müller <Rent>", "", "-1250.00"),
+        ("6f1c2d1e-0003", "2026-10-05", 0, "The Neighborhood Grocery Cooperative Market", "Weekly shopping", "-45.10")
+    ];
+
+    fn statement() -> (OfxStatement, OfxAccount) {
+        let mut balance = D64::ZERO;
+        let mut transactions = vec![];
+
+        for (id, date, check_number, vendor, memo, amount) in ENTRIES {
+            let mut transaction = Transaction::builder()
+            .set_date(date)
+            .set_check_number(check_number)
+            .set_vendor(vendor)
+            .set_memo(memo)
+            .build();
+
+            let amount = amount.parse::<D64>().expect("could not parse amount");
+            set_signed_amount(&mut transaction, amount);
+            balance += amount;
+
+            transactions.push(OfxTransaction { fitid: id.to_string(), record: Record::from(id, transaction) });
+        }
+
+        let date = transactions.last().map(|transaction| transaction.record.transaction.date).expect("register has no entries");
+
+        let statement = OfxStatement { transactions, ledger_balance: Some(LedgerBalance { amount: balance, date }) };
+        let account = OfxAccount { bank_id: "021000021".to_string(), account_id: "Checking".to_string(), account_type: OfxAccountType::Checking };
+
+        (statement, account)
+    }
+
+    #[test]
+    fn exports_required_elements_in_order() {
+        let (statement, account) = statement();
+        let ofx = statement.to_ofx(&account);
+
+        let elements = [
+            "<?xml", "<?OFX OFXHEADER=\"200\" VERSION=\"220\"", "<OFX>",
+            "<SIGNONMSGSRSV1>", "<SONRS>", "<STATUS>", "<CODE>", "<SEVERITY>", "</STATUS>", "<DTSERVER>", "<LANGUAGE>", "</SONRS>", "</SIGNONMSGSRSV1>",
+            "<BANKMSGSRSV1>", "<STMTTRNRS>", "<TRNUID>", "<STATUS>", "<CODE>", "<SEVERITY>", "</STATUS>",
+            "<STMTRS>", "<CURDEF>", "<BANKACCTFROM>", "<BANKID>", "<ACCTID>", "<ACCTTYPE>", "</BANKACCTFROM>",
+            "<BANKTRANLIST>", "<DTSTART>", "<DTEND>", "<STMTTRN>", "<STMTTRN>", "<STMTTRN>", "</BANKTRANLIST>",
+            "<LEDGERBAL>", "<BALAMT>", "<DTASOF>", "</LEDGERBAL>",
+            "</STMTRS>", "</STMTTRNRS>", "</BANKMSGSRSV1>", "</OFX>"
+        ];
+
+        let mut position = 0;
+
+        for element in elements {
+            let offset = ofx[position..].find(element).unwrap_or_else(|| panic!("{} is missing or out of order in {}", element, ofx));
+            position += offset + element.len();
+        }
+
+        assert_eq!(value(&ofx, "CURDEF").as_deref(), Some("USD"));
+        assert_eq!(value(&ofx, "BANKID").as_deref(), Some("021000021"));
+        assert_eq!(value(&ofx, "ACCTID").as_deref(), Some("Checking"));
+        assert_eq!(value(&ofx, "ACCTTYPE").as_deref(), Some("CHECKING"));
+        assert_eq!(value(&ofx, "DTSTART").as_deref(), Some("20261001"));
+        assert_eq!(value(&ofx, "DTEND").as_deref(), Some("20261005"));
+
+        // the ledger balance is the balance after the last entry.
+        assert_eq!(value(&ofx, "BALAMT").as_deref(), Some("204.90"));
+        assert_eq!(value(&ofx, "DTASOF").as_deref(), Some("20261005"));
+
+        for transaction in aggregates(&ofx, "STMTTRN") {
+            let positions: Vec<usize> = ["<TRNTYPE>", "<DTPOSTED>", "<TRNAMT>", "<FITID>", "<CHECKNUM>", "<NAME>", "<MEMO>"].iter().filter_map(|element| transaction.find(element)).collect();
+
+            assert!(positions.is_sorted(), "elements are out of order in {}", transaction);
+            assert!(["TRNTYPE", "DTPOSTED", "TRNAMT", "FITID"].iter().all(|element| value(transaction, element).is_some()), "required elements are missing from {}", transaction);
+        }
+    }
+
+    #[test]
+    fn maps_transaction_types() {
+        let (statement, account) = statement();
+        let ofx = statement.to_ofx(&account);
+
+        let transaction_types: Vec<Option<String>> = aggregates(&ofx, "STMTTRN").iter().map(|transaction| value(transaction, "TRNTYPE")).collect();
+        let check_numbers: Vec<Option<String>> = aggregates(&ofx, "STMTTRN").iter().map(|transaction| value(transaction, "CHECKNUM")).collect();
+
+        assert_eq!(transaction_types, vec![Some("CREDIT".to_string()), Some("CHECK".to_string()), Some("DEBIT".to_string())]);
+        assert_eq!(check_numbers, vec![None, Some("1042".to_string()), None]);
+    }
+
+    #[test]
+    fn cuts_names_short() {
+        let (statement, account) = statement();
+        let ofx = statement.to_ofx(&account);
+
+        let names: Vec<String> = aggregates(&ofx, "STMTTRN").iter().filter_map(|transaction| value(transaction, "NAME")).collect();
+
+        assert_eq!(names, vec!["ACME Payroll & Benefits", "Hausverwaltung Müller <Rent>", "The Neighborhood Grocery Coopera"]);
+        assert!(names.iter().all(|name| name.chars().count() <= MAX_NAME_LENGTH));
+    }
+
+    #[test]
+    fn reads_exported_statement() {
+        let (statement, account) = statement();
+        let (statements, invalid_transactions) = OfxStatement::parse(&statement.to_ofx(&account)).expect("could not read exported statement");
+
+        assert!(invalid_transactions.is_empty());
+        assert_eq!(statements.len(), 1);
+
+        let transactions = &statements[0].transactions;
+        let fitids: Vec<&str> = transactions.iter().map(|transaction| transaction.fitid.as_str()).collect();
+        let dates: Vec<String> = transactions.iter().map(|transaction| transaction.record.transaction.date.format("%Y-%m-%d").to_string()).collect();
+        let check_numbers: Vec<Option<u32>> = transactions.iter().map(|transaction| transaction.record.transaction.check_number.filter(|check_number| *check_number > 0)).collect();
+        let memos: Vec<&str> = transactions.iter().map(|transaction| transaction.record.transaction.memo.as_str()).collect();
+        let amounts: Vec<String> = transactions.iter().map(|transaction| format!("{:.2}", signed_amount(&transaction.record.transaction))).collect();
+
+        assert_eq!(fitids, ENTRIES.map(|(id, ..)| id));
+        assert_eq!(dates, ENTRIES.map(|(_, date, ..)| date));
+        assert_eq!(check_numbers, vec![None, Some(1042), None]);
+        assert_eq!(memos, ENTRIES.map(|(.., memo, _)| memo));
+        assert_eq!(amounts, ENTRIES.map(|(.., amount)| amount));
+
+        let balance = statements[0].ledger_balance.as_ref().expect("ledger balance is missing");
+
+        assert_eq!(format!("{:.2}", balance.amount), "204.90");
+        assert_eq!(balance.date.format("%Y-%m-%d").to_string(), "2026-10-05");
+    }
+}
@@ -0,0 +1,207 @@
+use chrono::{ Local, NaiveDate };
+use clap::Parser;
+use crate::{ amount::signed_amount, database::*, errors::CheckbookError, ledger_entry::LedgerEntry, reconciliation::Reconciliation, shared::parse_date, status::Status };
+use fastnum::D64;
+use std::io::{ self, Write };
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Reconcile the register against a bank statement. \r\n\r\nTo see what has not been reconciled yet, give the end date and ending balance of the statement, like this: \r\n\r\nrcheckbook reconcile -d 2026-09-30 -b 1520.75 \r\n\r\nThis lists the unreconciled entries up to that date, along with the difference between the cleared balance and the statement balance. \r\n\r\nEntries already marked as cleared count towards the cleared balance, and are reconciled along with the entries that get marked. \r\n\r\nEntries that appear on the statement can then be marked by giving their identifiers with -i, separated by commas or by giving -i more than once, or one at a time with --interactive. \r\n\r\nMarks are only saved when the cleared balance matches the statement, so that a reconciliation is never left half done. Each completed reconciliation is recorded, and can be seen with --history. \r\n\r\nReconciliations cover every account combined, unless a single account is given with --account.")]
+pub struct Reconcile {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short, required_unless_present = "history")]
+    pub date: Option<String>,
+
+    #[clap(long, short, allow_hyphen_values = true, required_unless_present = "history")]
+    pub balance: Option<D64>,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long = "id", short, value_delimiter = ',')]
+    pub ids: Vec<String>,
+
+    #[clap(long, conflicts_with = "ids")]
+    pub interactive: bool,
+
+    #[clap(long, conflicts_with_all = ["date", "balance", "ids", "interactive"])]
+    pub history: bool
+}
+
+impl Reconcile {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+
+        if self.history {
+            for reconciliation in load_reconciliations_from_db(&db)? {
+                println!("{}", reconciliation);
+            }
+
+            return Ok(());
+        }
+
+        let (Some(date), Some(statement_balance)) = (&self.date, self.balance) else {
+            return Err(CheckbookError::InvalidInput("Please give the end date and ending balance of the statement.".to_string()));
+        };
+
+        let statement_date = parse_date(date)?;
+        let transaction = db.transaction()?;
+
+        let entries: Vec<LedgerEntry> = load_ledger_from_db(&transaction, &self.account)?.into_iter().filter(|entry| entry.record.transaction.date <= statement_date).collect();
+        let unreconciled: Vec<&LedgerEntry> = entries.iter().filter(|entry| entry.status != Status::Reconciled).collect();
+        let uncleared: Vec<&LedgerEntry> = unreconciled.iter().copied().filter(|entry| entry.status == Status::Uncleared).collect();
+
+        // the bank already has cleared entries, so they are part of the statement without being marked again.
+        let mut marked: Vec<&LedgerEntry> = unreconciled.iter().copied().filter(|entry| entry.status == Status::Cleared).collect();
+        let mut cleared_balance = entries.iter().filter(|entry| entry.status != Status::Uncleared).fold(D64::ZERO, |sum, entry| sum + signed_amount(&entry.record.transaction));
+
+        let selected: Vec<&LedgerEntry> = if self.interactive {
+            Self::prompt(&uncleared, cleared_balance, statement_balance)?
+        } else if !self.ids.is_empty() {
+            self.selected(&unreconciled, statement_date)?
+        } else {
+            for entry in &unreconciled {
+                println!("{}", Self::entry(entry));
+            }
+
+            println!();
+            vec![]
+        };
+
+        for entry in selected {
+            if !marked.iter().any(|marked_entry| marked_entry.record.id == entry.record.id) {
+                cleared_balance += signed_amount(&entry.record.transaction);
+                marked.push(entry);
+            }
+        }
+
+        let difference = statement_balance - cleared_balance;
+
+        println!("Cleared Balance\t{:.2}\r\nStatement Balance\t{:.2}\r\nDifference\t{:.2}", cleared_balance, statement_balance, difference);
+
+        if !self.interactive && self.ids.is_empty() {
+            return Ok(());
+        }
+
+        if !difference.round(2).is_zero() {
+            return Err(CheckbookError::Unbalanced(difference));
+        }
+
+        let marked_ids: Vec<String> = marked.iter().map(|entry| entry.record.id.clone()).collect();
+
+        mark_records_reconciled_in_db(&transaction, &marked_ids)?;
+        add_reconciliation_to_db(&transaction, &Reconciliation::from(&self.account, statement_date, statement_balance, Local::now().date_naive(), marked_ids.len() as u32))?;
+
+        transaction.commit()?;
+
+        println!("{} entries reconciled.", marked_ids.len());
+
+        Ok(())
+    }
+
+    /// retrieve the unreconciled entries with the given identifiers, making sure each one is on or before the statement date.
+    fn selected<'a>(&self, unreconciled: &[&'a LedgerEntry], statement_date: NaiveDate) -> Result<Vec<&'a LedgerEntry>, CheckbookError> {
+        let mut marked: Vec<&LedgerEntry> = vec![];
+
+        for id in &self.ids {
+            let Some(entry) = unreconciled.iter().find(|entry| entry.record.id.to_lowercase() == id.trim().to_lowercase()) else {
+                return Err(CheckbookError::InvalidInput(format!("entry {} is not an unreconciled entry on or before {}.", id.trim(), statement_date.format("%Y-%m-%d"))));
+            };
+
+            if !marked.iter().any(|marked_entry| marked_entry.record.id == entry.record.id) {
+                marked.push(entry);
+            }
+        }
+
+        Ok(marked)
+    }
+
+    /** ask about each uncleared entry in turn, returning the ones marked as cleared.
+     * Answering q, or reaching the end of input, stops asking.
+     */
+    fn prompt<'a>(uncleared: &[&'a LedgerEntry], cleared_balance: D64, statement_balance: D64) -> Result<Vec<&'a LedgerEntry>, CheckbookError> {
+        let mut marked: Vec<&LedgerEntry> = vec![];
+        let mut cleared_balance = cleared_balance;
+
+        for entry in uncleared {
+            print!("{}\tDifference {:.2}\tCleared? [y/n/q] ", Self::entry(entry), statement_balance - cleared_balance);
+            io::stdout().flush()?;
+
+            let mut answer = String::new();
+
+            if io::stdin().read_line(&mut answer)? == 0 {
+                println!();
+                break;
+            }
+
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => {
+                    cleared_balance += signed_amount(&entry.record.transaction);
+                    marked.push(entry);
+                },
+                "q" | "quit" => break,
+                _ => {}
+            }
+        }
+
+        println!();
+
+        Ok(marked)
+    }
+
+    fn entry(entry: &LedgerEntry) -> String {
+        let record = &entry.record;
+
+        format!("{}\t{}\t{}\t{}\t{:.2}", record.id, record.transaction.date.format("%Y-%m-%d"), entry.status.letter(), record.transaction.vendor, signed_amount(&record.transaction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bcheck::{ Record, Transaction };
+    use crate::amount::set_signed_amount;
+    use std::{ env, fs };
+
+    #[test]
+    fn reconciles_cleared_entries() {
+        let directory = env::temp_dir().join(format!("rcheckbook-reconcile-{}", std::process::id()));
+        let path = directory.join("register.db");
+        let p = path.to_str().expect("temporary directory is not valid UTF-8");
+
+        let db = copy_database_if_not_exists(p).expect("could not create database");
+
+        // a reconciled opening balance, a cleared and an uncleared entry on the statement, and an uncleared one that is not.
+        for (id, status, amount) in [("A", Status::Reconciled, "1000.00"), ("B", Status::Cleared, "-45.20"), ("C", Status::Uncleared, "-0.07"), ("D", Status::Uncleared, "-12.00")] {
+            let mut transaction = Transaction::builder().set_date("2026-09-15").set_vendor("Vendor").build();
+            set_signed_amount(&mut transaction, amount.parse::<D64>().expect("could not parse amount"));
+
+            add_record_to_db(&db, &Record::from(id, transaction), &None).expect("could not add entry");
+            set_record_status_in_db(&db, id, &status).expect("could not set status");
+        }
+
+        drop(db);
+
+        let reconcile = Reconcile {
+            file_path: p.to_string(),
+            date: Some("2026-09-30".to_string()),
+            balance: Some("954.73".parse::<D64>().expect("could not parse balance")),
+            account: None,
+            ids: vec!["C".to_string()],
+            interactive: false,
+            history: false
+        };
+
+        reconcile.run().expect("could not reconcile statement");
+
+        let db = open_database(p).expect("could not open database");
+        let statuses: Vec<(String, Status)> = load_ledger_from_db(&db, &None).expect("could not load ledger").into_iter().map(|entry| (entry.record.id, entry.status)).collect();
+
+        assert_eq!(statuses, vec![("A".to_string(), Status::Reconciled), ("B".to_string(), Status::Reconciled), ("C".to_string(), Status::Reconciled), ("D".to_string(), Status::Uncleared)]);
+        assert_eq!(load_reconciliations_from_db(&db).expect("could not load reconciliations")[0].entries, 2);
+
+        drop(db);
+        fs::remove_dir_all(&directory).expect("could not remove test directory");
+    }
+}
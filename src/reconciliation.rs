@@ -0,0 +1,32 @@
+use chrono::NaiveDate;
+use fastnum::D64;
+use std::fmt;
+
+/// Represents a completed reconciliation against a bank statement.
+pub struct Reconciliation {
+    pub id: u32,
+    pub account: Option<String>,
+    pub statement_date: NaiveDate,
+    pub statement_balance: D64,
+    pub reconciled_on: NaiveDate,
+    pub entries: u32
+}
+
+impl Reconciliation {
+    pub fn from(account: &Option<String>, statement_date: NaiveDate, statement_balance: D64, reconciled_on: NaiveDate, entries: u32) -> Self {
+        Self {
+            id: 0,
+            account: account.clone(),
+            statement_date,
+            statement_balance,
+            reconciled_on,
+            entries
+        }
+    }
+}
+
+impl fmt::Display for Reconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\t{}\t{:.2}\t{}\t{}", self.id, self.statement_date.format("%Y-%m-%d"), self.account.clone().unwrap_or("All Accounts".to_string()), self.statement_balance, self.entries, self.reconciled_on.format("%Y-%m-%d"))
+    }
+}
@@ -0,0 +1,84 @@
+use bcheck::Record;
+use std::fmt;
+use crate::{ amount::signed_amount, split::Split, status::Status };
+
+/// Represents a single field that differs between an entry in the register and the one being imported over it.
+pub struct FieldChange {
+    pub field: &'static str,
+    pub stored: String,
+    pub incoming: String
+}
+
+/// Represents how an entry being imported compares with what the register already has.
+pub enum RecordComparison {
+    New,
+    Identical,
+    Changed(Vec<FieldChange>)
+}
+
+impl RecordComparison {
+    /** compare an entry being imported with the stored entry that has the same identifier, if any.
+     * Splits are only compared when the file has some for the entry,
+     * since imports leave the splits of an entry alone otherwise.
+     */
+    pub fn from(stored: Option<(&Record, &Status, &[Split])>, incoming: (&Record, &Status, Option<&[Split]>)) -> Self {
+        let Some((stored_record, stored_status, stored_splits)) = stored else {
+            return Self::New;
+        };
+
+        let (incoming_record, incoming_status, incoming_splits) = incoming;
+        let stored_fields = fields(stored_record, stored_status);
+        let incoming_fields = fields(incoming_record, incoming_status);
+
+        let mut changes: Vec<FieldChange> = stored_fields.into_iter().zip(incoming_fields).filter(|((_, stored), (_, incoming))| stored != incoming).map(|((field, stored), (_, incoming))| FieldChange { field, stored, incoming }).collect();
+
+        if let Some(incoming_splits) = incoming_splits && incoming_splits != stored_splits {
+            changes.push(FieldChange {
+                field: "splits",
+                stored: splits_text(stored_splits),
+                incoming: splits_text(incoming_splits)
+            });
+        }
+
+        if changes.is_empty() { Self::Identical } else { Self::Changed(changes) }
+    }
+
+    /// retrieve the name the comparison is shown with in previews.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Identical => "identical",
+            Self::Changed(_) => "changed"
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\t->\t{}", self.field, self.stored, self.incoming)
+    }
+}
+
+/// retrieve each field of an entry that an import can change, in the order they are listed.
+fn fields(record: &Record, status: &Status) -> [(&'static str, String); 7] {
+    let transaction = &record.transaction;
+
+    [
+        ("date", transaction.date.format("%Y-%m-%d").to_string()),
+        ("check", transaction.check_number.filter(|check_number| *check_number > 0).map(|check_number| check_number.to_string()).unwrap_or_default()),
+        ("status", status.to_string()),
+        ("category", transaction.category.clone().unwrap_or_default()),
+        ("vendor", transaction.vendor.clone()),
+        ("memo", transaction.memo.clone()),
+        ("amount", format!("{:.2}", signed_amount(transaction)))
+    ]
+}
+
+/// present splits the way they are given to the add command, such as Groceries=-45.20=Produce.
+fn splits_text(splits: &[Split]) -> String {
+    splits.iter().map(|split| {
+        let memo = if split.memo.is_empty() { String::new() } else { format!("={}", split.memo) };
+
+        format!("{}={:.2}{}", split.category.as_deref().unwrap_or("Uncategorized"), split.amount, memo)
+    }).collect::<Vec<String>>().join(", ")
+}
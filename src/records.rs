@@ -1,17 +1,29 @@
-use bcheck::Record;
-use crate::database::*;
+use bcheck::{ Record, TransactionType };
+use crate::{ category_path::is_within, ledger_entry::LedgerEntry, split::Split, status::Status };
+use fastnum::D64;
+use std::collections::HashMap;
 
 pub struct Records {
-    records: Vec<Record>
+    records: Vec<Record>,
+    balances: HashMap<String, D64>,
+    statuses: HashMap<String, Status>
 }
 
 impl Records {
-    pub fn from(r: Vec<Record>) -> Records {
+    pub fn from(entries: Vec<LedgerEntry>) -> Records {
+        let balances = entries.iter().map(|entry| (entry.record.id.to_lowercase(), entry.balance)).collect();
+        let statuses = entries.iter().map(|entry| (entry.record.id.to_lowercase(), entry.status.clone())).collect();
+
         Records { 
-            records: r 
+            records: entries.into_iter().map(|entry| entry.record).collect(),
+            balances,
+            statuses
         }
     }
     
+    /** retrieve the records in date order.
+     * The sort is stable, so entries on the same day stay in the order of the ledger.
+     */
     pub fn sorted_records(&self) -> Vec<Record> {
         let mut stored_records = self.records.clone();
         stored_records.sort_by_key(|r| r.transaction.date);
@@ -19,7 +31,45 @@ impl Records {
         stored_records
     }
 
-    pub fn balance_for_record(&self, db: &str, r: &Record) -> f64 {
-        retrieve_balance_for_record(db, r.clone())
+    /// retrieve the running balance at the given record, as it was loaded from the ledger.
+    pub fn balance_for_record(&self, r: &Record) -> D64 {
+        self.balances.get(&r.id.to_lowercase()).copied().unwrap_or(D64::ZERO)
+    }
+
+    /// retrieve the status of the given record, as it was loaded from the ledger.
+    pub fn status_for_record(&self, r: &Record) -> Status {
+        self.statuses.get(&r.id.to_lowercase()).cloned().unwrap_or(Status::from_reconciled(r.transaction.is_reconciled))
+    }
+
+    /** retrieve the records in date order that match the given filters, as used by list and export.
+     * Categories match nested categories and splits, while vendors and memos match any part of the text.
+     */
+    pub fn filtered_records(&self, splits: &HashMap<String, Vec<Split>>, category: &Option<String>, vendor: &Option<String>, memo: &Option<String>, statuses: &[Status], transaction_type: &Option<TransactionType>) -> Vec<Record> {
+        let mut filtered_records: Vec<Record> = self.sorted_records();
+        if let Some(category) = category {
+            filtered_records.retain(|record| if let Some(record_splits) = splits.get(&record.id.to_lowercase()) {
+                record_splits.iter().any(|split| is_within(&split.category.clone().unwrap_or("Uncategorized".to_string()), category) || split.category.clone().unwrap_or("Uncategorized".to_string()).to_lowercase().contains(&category.to_string().to_lowercase()))
+            } else {
+                is_within(&record.transaction.category.clone().unwrap_or("Uncategorized".to_string()), category) || record.transaction.category.clone().unwrap_or("Uncategorized".to_string()).to_lowercase().contains(&category.to_string().to_lowercase())
+            });
+        }
+
+        if let Some(vendor) = vendor {
+            filtered_records.retain(|record| record.transaction.vendor.to_lowercase() == vendor.to_lowercase() || record.transaction.vendor.to_lowercase().contains(&vendor.to_string().to_lowercase()));
+        }
+
+        if let Some(memo) = memo {
+            filtered_records.retain(|record| record.transaction.memo.to_lowercase() == memo.to_lowercase() || record.transaction.memo.to_lowercase().contains(&memo.to_string().to_lowercase()));
+        }
+
+        if !statuses.is_empty() {
+            filtered_records.retain(|record| statuses.contains(&self.status_for_record(record)));
+        }
+
+        if let Some(record_type) = transaction_type {
+            filtered_records.retain(|record| record.transaction.transaction_type == *record_type);
+        }
+
+        filtered_records
     }
 }
\ No newline at end of file
@@ -0,0 +1,81 @@
+use chrono::{ Datelike, Duration, Months, NaiveDate, Weekday };
+use clap::ValueEnum;
+use crate::shared::display_value_name;
+
+/// Represents how often a scheduled entry comes around.
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum Recurrence {
+    Weekly,
+    Biweekly,
+    Monthly,
+    LastBusinessDay
+}
+
+impl Recurrence {
+    /** retrieve every date from start through the given date that the recurrence falls on.
+     * Weekly and biweekly recurrences count from the start date.
+     * Monthly recurrences fall on the given day, or the last day of months that are too short,
+     * and use the day of the start date when no day is given.
+     */
+    pub fn occurrences(&self, start: NaiveDate, day: Option<u32>, through: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = vec![];
+
+        match self {
+            Self::Weekly | Self::Biweekly => {
+                let step = if let Self::Weekly = self { Duration::weeks(1) } else { Duration::weeks(2) };
+                let mut date = start;
+
+                while date <= through {
+                    dates.push(date);
+                    date += step;
+                }
+            },
+            Self::Monthly | Self::LastBusinessDay => {
+                let mut month = start.with_day(1).unwrap_or(start);
+
+                while month <= through {
+                    let date = if let Self::Monthly = self {
+                        day_in_month(month, day.unwrap_or(start.day()))
+                    } else {
+                        last_business_day(month)
+                    };
+
+                    if date >= start && date <= through {
+                        dates.push(date);
+                    }
+
+                    month = month + Months::new(1);
+                }
+            }
+        }
+
+        dates
+    }
+}
+
+display_value_name!(Recurrence);
+
+/// retrieve the last day of the month that the given date is in.
+fn last_day_of_month(date: NaiveDate) -> NaiveDate {
+    let first = date.with_day(1).unwrap_or(date);
+
+    first + Months::new(1) - Duration::days(1)
+}
+
+/// retrieve the given day of the month, or the last day if the month is too short.
+fn day_in_month(month: NaiveDate, day: u32) -> NaiveDate {
+    let last = last_day_of_month(month);
+
+    last.with_day(day.min(last.day())).unwrap_or(last)
+}
+
+/// retrieve the last weekday of the month, without accounting for holidays.
+fn last_business_day(month: NaiveDate) -> NaiveDate {
+    let mut date = last_day_of_month(month);
+
+    while matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+        date -= Duration::days(1);
+    }
+
+    date
+}
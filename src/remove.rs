@@ -1,5 +1,6 @@
 use clap::Parser;
-use crate::database::*;
+use rusqlite::Connection;
+use crate::{ database::*, errors::CheckbookError };
 
 #[derive(Parser)]
 #[clap(version = "0.3", author = "Bryce Campbell", about = "Remove a specified transaction from the register.")]
@@ -12,11 +13,18 @@ pub struct Remove {
 }
 
 impl Remove {
-    pub fn run(&self) {
-        self.remove_record(&self.file_path);
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = open_database(&self.file_path)?;
+        let transaction = db.transaction()?;
+
+        self.remove_record(&transaction)?;
+
+        transaction.commit()?;
+
+        Ok(())
     }
 
-    fn remove_record(&self, p: &str) {
-        delete_record_from_db(p, &self.id);
+    fn remove_record(&self, db: &Connection) -> Result<(), CheckbookError> {
+        delete_record_from_db(db, &self.id)
     }
 }
\ No newline at end of file
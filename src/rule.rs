@@ -0,0 +1,145 @@
+use std::fmt;
+use bcheck::{ Transaction, TransactionType };
+use fastnum::D64;
+use regex::Regex;
+use crate::errors::CheckbookError;
+
+/// Represents text that a rule looks for in the vendor or memo of an entry.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Contains(String),
+    Regex(Regex)
+}
+
+impl Pattern {
+    /// create a pattern, which is a regular expression when is_regex is true and plain text otherwise.
+    pub fn from(text: &str, is_regex: bool) -> Result<Self, CheckbookError> {
+        if is_regex {
+            Regex::new(text).map(Self::Regex).map_err(|error| CheckbookError::Parse(format!("{} is not a valid regular expression: {}", text, error)))
+        } else {
+            Ok(Self::Contains(text.to_string()))
+        }
+    }
+
+    /// check if the given text matches. Plain text matches anywhere in the text, ignoring case.
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Contains(pattern) => text.to_lowercase().contains(&pattern.to_lowercase()),
+            Self::Regex(pattern) => pattern.is_match(text)
+        }
+    }
+
+    pub fn is_regex(&self) -> bool {
+        matches!(self, Self::Regex(_))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Contains(pattern) => pattern,
+            Self::Regex(pattern) => pattern.as_str()
+        }
+    }
+}
+
+/** Represents a rule that tidies up entries as they are added or imported.
+ * Every condition that is given has to match for the rule to apply.
+ * Amounts are compared without their sign, so use the transaction type to tell deposits from withdrawals.
+ */
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub id: u32,
+    pub vendor_pattern: Option<Pattern>,
+    pub memo_pattern: Option<Pattern>,
+    pub minimum: Option<D64>,
+    pub maximum: Option<D64>,
+    pub transaction_type: Option<TransactionType>,
+    pub category: Option<String>,
+    pub vendor: Option<String>,
+    pub memo: Option<String>
+}
+
+impl Rule {
+    /// check if the rule has anything to match on.
+    pub fn has_conditions(&self) -> bool {
+        self.vendor_pattern.is_some() || self.memo_pattern.is_some() || self.minimum.is_some() || self.maximum.is_some() || self.transaction_type.is_some()
+    }
+
+    /// check if the rule has anything to change.
+    pub fn has_actions(&self) -> bool {
+        self.category.is_some() || self.vendor.is_some() || self.memo.is_some()
+    }
+
+    pub fn matches(&self, t: &Transaction) -> bool {
+        self.vendor_pattern.as_ref().is_none_or(|pattern| pattern.is_match(&t.vendor)) &&
+        self.memo_pattern.as_ref().is_none_or(|pattern| pattern.is_match(&t.memo)) &&
+        self.minimum.is_none_or(|minimum| t.amount >= minimum) &&
+        self.maximum.is_none_or(|maximum| t.amount <= maximum) &&
+        self.transaction_type.as_ref().is_none_or(|transaction_type| &t.transaction_type == transaction_type)
+    }
+
+    /** apply the changes of the rule to the given transaction, returning whether anything changed.
+     * A category is only filled in for transactions without one,
+     * so rules never undo a category that was chosen by hand, nor touch entries with splits.
+     */
+    pub fn apply(&self, t: &mut Transaction, has_splits: bool) -> bool {
+        let original = t.clone();
+
+        if let Some(category) = &self.category {
+            let is_uncategorized = t.category.as_ref().is_none_or(|category| category.is_empty() || category.to_lowercase() == "uncategorized");
+
+            if is_uncategorized && !has_splits {
+                t.category = Some(category.clone());
+            }
+        }
+
+        if let Some(vendor) = &self.vendor {
+            t.vendor = vendor.clone();
+        }
+
+        if let Some(memo) = &self.memo {
+            t.memo = memo.clone();
+        }
+
+        *t != original
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut conditions = vec![];
+        let mut actions = vec![];
+
+        for (field, pattern) in [("vendor", &self.vendor_pattern), ("memo", &self.memo_pattern)] {
+            if let Some(pattern) = pattern {
+                conditions.push(format!("{} {} {}", field, if pattern.is_regex() { "matches" } else { "contains" }, pattern.as_str()));
+            }
+        }
+
+        if let Some(minimum) = self.minimum {
+            conditions.push(format!("amount at least {:.2}", minimum));
+        }
+
+        if let Some(maximum) = self.maximum {
+            conditions.push(format!("amount at most {:.2}", maximum));
+        }
+
+        if let Some(transaction_type) = &self.transaction_type {
+            conditions.push(if let TransactionType::Deposit = transaction_type { "deposit" } else { "withdrawal" }.to_string());
+        }
+
+        for (field, value) in [("category", &self.category), ("vendor", &self.vendor), ("memo", &self.memo)] {
+            if let Some(value) = value {
+                actions.push(format!("set {} to {}", field, value));
+            }
+        }
+
+        write!(f, "{}\t{}\t{}", self.id, conditions.join(", "), actions.join(", "))
+    }
+}
+
+/** apply the first of the given rules that matches the transaction, returning whether anything changed.
+ * Rules are tried in the order they were added.
+ */
+pub fn apply_rules(rules: &[Rule], t: &mut Transaction, has_splits: bool) -> bool {
+    rules.iter().find(|rule| rule.matches(t)).is_some_and(|rule| rule.apply(t, has_splits))
+}
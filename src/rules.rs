@@ -0,0 +1,194 @@
+use bcheck::{ Transaction, TransactionType };
+use clap::Parser;
+use crate::{ database::*, errors::CheckbookError, rule::* };
+use fastnum::D64;
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage rules that fill in categories and tidy up vendors and memos. \r\n\r\nA rule matches entries on their vendor, memo, amount, and type, then sets their category, vendor, or memo. For example, this files anything from a vendor containing AMZN under Shopping, and gives it a tidier vendor: \r\n\r\nrcheckbook rules add --vendor AMZN --set-category Shopping --set-vendor Amazon \r\n\r\nPatterns match anywhere in the text, ignoring case, unless --regex is given to treat them as regular expressions. Amounts are compared without their sign, so use -t to tell deposits from withdrawals. \r\n\r\nRules are applied when entries are added or imported, and the first rule that matches an entry is used. A category is only filled in for entries without one. \r\n\r\nTo apply rules to entries already in the register, and see what would change first, do this: \r\n\r\nrcheckbook rules apply --dry-run")]
+pub struct Rules {
+    #[clap(subcommand)]
+    pub subcommand: RulesCommand
+}
+
+#[derive(Parser)]
+pub enum RulesCommand {
+    List(ListRules),
+    Add(AddRule),
+    Delete(DeleteRule),
+    Apply(ApplyRules)
+}
+
+#[derive(Parser)]
+#[clap(about = "List rules in the order they are tried.")]
+pub struct ListRules {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Add a new rule, which is tried after every existing rule.")]
+pub struct AddRule {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub vendor: Option<String>,
+
+    #[clap(long, short)]
+    pub memo: Option<String>,
+
+    #[clap(long)]
+    pub regex: bool,
+
+    #[clap(long)]
+    pub minimum: Option<D64>,
+
+    #[clap(long)]
+    pub maximum: Option<D64>,
+
+    #[clap(long, short)]
+    pub transaction_type: Option<TransactionType>,
+
+    #[clap(long)]
+    pub set_category: Option<String>,
+
+    #[clap(long)]
+    pub set_vendor: Option<String>,
+
+    #[clap(long)]
+    pub set_memo: Option<String>
+}
+
+#[derive(Parser)]
+#[clap(about = "Delete a rule.")]
+pub struct DeleteRule {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub id: u32
+}
+
+#[derive(Parser)]
+#[clap(about = "Apply rules to the entries already in the register.")]
+pub struct ApplyRules {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub dry_run: bool,
+
+    #[clap(long)]
+    pub account: Option<String>
+}
+
+impl Rules {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            RulesCommand::List(l) => l.run(),
+            RulesCommand::Add(a) => a.run(),
+            RulesCommand::Delete(d) => d.run(),
+            RulesCommand::Apply(a) => a.run()
+        }
+    }
+}
+
+impl ListRules {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        for rule in load_rules_from_db(&db)? {
+            println!("{}", rule);
+        }
+
+        Ok(())
+    }
+}
+
+impl AddRule {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        let rule = Rule {
+            id: 0,
+            vendor_pattern: self.vendor.as_ref().map(|vendor| Pattern::from(vendor, self.regex)).transpose()?,
+            memo_pattern: self.memo.as_ref().map(|memo| Pattern::from(memo, self.regex)).transpose()?,
+            minimum: self.minimum,
+            maximum: self.maximum,
+            transaction_type: self.transaction_type.clone(),
+            category: self.set_category.clone(),
+            vendor: self.set_vendor.clone(),
+            memo: self.set_memo.clone()
+        };
+
+        if !rule.has_conditions() {
+            Err(CheckbookError::InvalidInput("Please give at least one of --vendor, --memo, --minimum, --maximum, or -t to match on.".to_string()))
+        } else if !rule.has_actions() {
+            Err(CheckbookError::InvalidInput("Please give at least one of --set-category, --set-vendor, or --set-memo.".to_string()))
+        } else if let (Some(minimum), Some(maximum)) = (self.minimum, self.maximum) && minimum > maximum {
+            Err(CheckbookError::InvalidInput(format!("The minimum of {:.2} is more than the maximum of {:.2}.", minimum, maximum)))
+        } else {
+            add_rule_to_db(&db, &rule)
+        }
+    }
+}
+
+impl DeleteRule {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        delete_rule_from_db(&db, self.id)
+    }
+}
+
+impl ApplyRules {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+        let transaction = db.transaction()?;
+
+        let rules = load_rules_from_db(&transaction)?;
+        let transfers = load_transfer_ids_from_db(&transaction)?;
+        let splits = load_splits_from_db(&transaction)?;
+        let mut changed_entries = 0;
+
+        for record in load_records_from_db(&transaction, &self.account)?.into_iter().filter(|record| !transfers.contains(&record.id.to_lowercase())) {
+            let mut changed_record = record.clone();
+
+            if apply_rules(&rules, &mut changed_record.transaction, splits.contains_key(&record.id.to_lowercase())) {
+                for change in changes(&record.transaction, &changed_record.transaction) {
+                    println!("{}\t{}", record.id, change);
+                }
+
+                if !self.dry_run {
+                    update_record_in_db(&transaction, &changed_record)?;
+                }
+
+                changed_entries += 1;
+            }
+        }
+
+        if self.dry_run {
+            println!("{} entries would be changed.", changed_entries);
+        } else {
+            transaction.commit()?;
+            println!("{} entries changed.", changed_entries);
+        }
+
+        Ok(())
+    }
+}
+
+/// describe each field that differs between the two transactions.
+fn changes(original: &Transaction, changed: &Transaction) -> Vec<String> {
+    let mut changes = vec![];
+    let original_category = original.category.clone().unwrap_or("Uncategorized".to_string());
+    let changed_category = changed.category.clone().unwrap_or("Uncategorized".to_string());
+
+    for (field, from, to) in [("category", &original_category, &changed_category), ("vendor", &original.vendor, &changed.vendor), ("memo", &original.memo, &changed.memo)] {
+        if from != to {
+            changes.push(format!("{}\t{} -> {}", field, from, to));
+        }
+    }
+
+    changes
+}
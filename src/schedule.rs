@@ -0,0 +1,250 @@
+use chrono::Local;
+use clap::Parser;
+use crate::{ database::*, errors::CheckbookError, recurrence::Recurrence, scheduled_entry::ScheduledEntry, shared::parse_date };
+use fastnum::D64;
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Manage entries that come around on a schedule, such as rent, paychecks, and subscriptions. \r\n\r\nA schedule can be created like this: \r\n\r\nrcheckbook schedule create --vendor \"Landlord\" -a -1200 --category Rent --recurrence monthly --day 1 \r\n\r\nSchedules can recur weekly, biweekly, monthly on a given day, or on the last business day of each month. Weekly and biweekly schedules count from the start date, which is today unless --start is given. \r\n\r\nTo add the entries that have come due, do this: \r\n\r\nrcheckbook schedule post --through 2026-10-31 \r\n\r\nEach occurrence is only ever posted once, so posting again adds nothing new. \r\n\r\nTo see upcoming entries without posting them, use rcheckbook list --upcoming.")]
+pub struct Schedule {
+    #[clap(subcommand)]
+    pub subcommand: ScheduleCommand
+}
+
+#[derive(Parser)]
+pub enum ScheduleCommand {
+    Create(CreateSchedule),
+    List(ListSchedules),
+    Edit(EditSchedule),
+    Delete(DeleteSchedule),
+    Post(PostSchedules)
+}
+
+#[derive(Parser)]
+#[clap(about = "Create a new schedule.")]
+pub struct CreateSchedule {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub vendor: String,
+
+    #[clap(long, short, default_value = "")]
+    pub memo: String,
+
+    #[clap(long, short, allow_hyphen_values = true, default_value = "0.0")]
+    pub amount: D64,
+
+    #[clap(long)]
+    pub category: Option<String>,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long, value_enum)]
+    pub recurrence: Recurrence,
+
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..=31))]
+    pub day: Option<u32>,
+
+    #[clap(long)]
+    pub start: Option<String>
+}
+
+#[derive(Parser)]
+#[clap(about = "List schedules along with when they next come due.")]
+pub struct ListSchedules {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String
+}
+
+#[derive(Parser)]
+#[clap(about = "Change an existing schedule.")]
+pub struct EditSchedule {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub id: u32,
+
+    #[clap(long)]
+    pub vendor: Option<String>,
+
+    #[clap(long, short)]
+    pub memo: Option<String>,
+
+    #[clap(long, short, allow_hyphen_values = true)]
+    pub amount: Option<D64>,
+
+    #[clap(long)]
+    pub category: Option<String>,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long, value_enum)]
+    pub recurrence: Option<Recurrence>,
+
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..=31))]
+    pub day: Option<u32>,
+
+    #[clap(long)]
+    pub start: Option<String>
+}
+
+#[derive(Parser)]
+#[clap(about = "Delete a schedule. Entries that were already posted are kept.")]
+pub struct DeleteSchedule {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub id: u32
+}
+
+#[derive(Parser)]
+#[clap(about = "Add an entry for every occurrence that has come due and has not been posted yet.")]
+pub struct PostSchedules {
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long)]
+    pub through: Option<String>
+}
+
+impl Schedule {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        match &self.subcommand {
+            ScheduleCommand::Create(c) => c.run(),
+            ScheduleCommand::List(l) => l.run(),
+            ScheduleCommand::Edit(e) => e.run(),
+            ScheduleCommand::Delete(d) => d.run(),
+            ScheduleCommand::Post(p) => p.run()
+        }
+    }
+}
+
+impl CreateSchedule {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        let schedule = ScheduledEntry {
+            id: 0,
+            vendor: self.vendor.clone(),
+            memo: self.memo.clone(),
+            amount: self.amount,
+            category: self.category.clone(),
+            account: self.account.clone(),
+            recurrence: self.recurrence.clone(),
+            day: self.day,
+            start: match &self.start {
+                Some(start) => parse_date(start)?,
+                None => Local::now().date_naive()
+            },
+            last_posted: None
+        };
+
+        validate(&schedule)?;
+        add_schedule_to_db(&db, &schedule)
+    }
+}
+
+impl ListSchedules {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        for schedule in load_schedules_from_db(&db)? {
+            println!("{}", schedule);
+        }
+
+        Ok(())
+    }
+}
+
+impl EditSchedule {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        let mut schedule = retrieve_schedule_from_db(&db, self.id)?;
+
+        if let Some(vendor) = &self.vendor {
+            schedule.vendor = vendor.clone();
+        }
+
+        if let Some(memo) = &self.memo {
+            schedule.memo = memo.clone();
+        }
+
+        if let Some(amount) = self.amount {
+            schedule.amount = amount;
+        }
+
+        if let Some(category) = &self.category {
+            schedule.category = Some(category.clone());
+        }
+
+        if let Some(account) = &self.account {
+            schedule.account = Some(account.clone());
+        }
+
+        if let Some(recurrence) = &self.recurrence {
+            schedule.recurrence = recurrence.clone();
+
+            if *recurrence != Recurrence::Monthly {
+                schedule.day = None;
+            }
+        }
+
+        if let Some(day) = self.day {
+            schedule.day = Some(day);
+        }
+
+        if let Some(start) = &self.start {
+            schedule.start = parse_date(start)?;
+        }
+
+        validate(&schedule)?;
+        update_schedule_in_db(&db, &schedule)
+    }
+}
+
+impl DeleteSchedule {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+
+        delete_schedule_from_db(&db, self.id)
+    }
+}
+
+impl PostSchedules {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let through = match &self.through {
+            Some(through) => parse_date(through)?,
+            None => Local::now().date_naive()
+        };
+
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+        let transaction = db.transaction()?;
+        let mut posted_entries = 0;
+
+        for schedule in load_schedules_from_db(&transaction)? {
+            for record in post_schedule_to_db(&transaction, &schedule, through)? {
+                println!("Posted {} on {} for {:.2}", schedule.vendor, record.transaction.date.format("%Y-%m-%d"), schedule.amount);
+                posted_entries += 1;
+            }
+        }
+
+        transaction.commit()?;
+
+        println!("{} entries posted.", posted_entries);
+
+        Ok(())
+    }
+}
+
+/// check that only monthly schedules are given a day of the month.
+fn validate(schedule: &ScheduledEntry) -> Result<(), CheckbookError> {
+    if schedule.day.is_some() && schedule.recurrence != Recurrence::Monthly {
+        Err(CheckbookError::InvalidInput("A day of the month can only be given to monthly schedules.".to_string()))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use bcheck::{ Record, Transaction };
+use chrono::{ Duration, Months, NaiveDate };
+use fastnum::D64;
+use std::fmt;
+use crate::{ amount::set_signed_amount, recurrence::Recurrence };
+
+/** Represents an entry that comes around on a schedule, such as rent or a paycheck.
+ * Like amounts given to the add command,
+ * the amount is negative for withdrawals and positive for deposits.
+ */
+#[derive(Clone, Debug)]
+pub struct ScheduledEntry {
+    pub id: u32,
+    pub vendor: String,
+    pub memo: String,
+    pub amount: D64,
+    pub category: Option<String>,
+    pub account: Option<String>,
+    pub recurrence: Recurrence,
+    pub day: Option<u32>,
+    pub start: NaiveDate,
+    pub last_posted: Option<NaiveDate>
+}
+
+impl ScheduledEntry {
+    /// retrieve the dates through the given date that have not been posted yet.
+    pub fn due_dates(&self, through: NaiveDate) -> Vec<NaiveDate> {
+        self.recurrence.occurrences(self.start, self.day, through).into_iter().filter(|date| self.last_posted.is_none_or(|posted| *date > posted)).collect()
+    }
+
+    /** retrieve the dates to forecast the schedule on, from the day after today through the given date.
+     * Occurrences that are overdue are expected to come through on the first day,
+     * but only once, since a schedule that has fallen behind is not going to be paid several times over at once.
+     */
+    pub fn forecast_dates(&self, today: NaiveDate, through: NaiveDate) -> Vec<NaiveDate> {
+        let first_day = today + Duration::days(1);
+        let due_dates = self.due_dates(through);
+        let is_overdue = due_dates.iter().any(|date| *date < first_day);
+
+        let mut dates: Vec<NaiveDate> = due_dates.into_iter().filter(|date| *date >= first_day).collect();
+
+        if is_overdue {
+            dates.insert(0, first_day);
+        }
+
+        dates
+    }
+
+    /// retrieve the first date that has not been posted yet.
+    pub fn next_date(&self) -> Option<NaiveDate> {
+        let from = self.last_posted.unwrap_or(self.start).max(self.start);
+
+        self.due_dates(from + Months::new(2)).first().copied()
+    }
+
+    /** create the entry for the given date.
+     * Projected entries are identified by their schedule and date,
+     * while posted entries get an identifier of their own.
+     */
+    pub fn record(&self, id: &str, date: NaiveDate) -> Record {
+        let mut transaction = Transaction::builder()
+        .set_date(&date.format("%Y-%m-%d").to_string())
+        .set_category(self.category.as_deref().unwrap_or_default())
+        .set_vendor(&self.vendor)
+        .set_memo(&self.memo)
+        .build();
+
+        set_signed_amount(&mut transaction, self.amount);
+
+        Record::from(id, transaction)
+    }
+}
+
+impl fmt::Display for ScheduledEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let recurrence = match (&self.recurrence, self.day) {
+            (Recurrence::Monthly, Some(day)) => format!("monthly on day {}", day),
+            (recurrence, _) => recurrence.to_string()
+        };
+
+        let next_date = self.next_date().map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_default();
+
+        write!(f, "{}\t{}\t{}\t{}\t{}\t{}\t{:.2}", self.id, recurrence, next_date, self.account.clone().unwrap_or_default(), self.category.clone().unwrap_or("Uncategorized".to_string()), self.vendor, self.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forecasts_overdue_occurrences_once() {
+        let date = |text: &str| NaiveDate::parse_from_str(text, "%Y-%m-%d").expect("could not parse date");
+
+        // rent that was due on the fifteenth of every month since January, and has never been posted.
+        let mut schedule = ScheduledEntry {
+            id: 1,
+            vendor: "Landlord".to_string(),
+            memo: String::new(),
+            amount: "-1250.00".parse::<D64>().expect("could not parse amount"),
+            category: Some("Rent".to_string()),
+            account: None,
+            recurrence: Recurrence::Monthly,
+            day: Some(15),
+            start: date("2026-01-15"),
+            last_posted: None
+        };
+
+        assert_eq!(schedule.forecast_dates(date("2026-10-18"), date("2026-11-17")), vec![date("2026-10-19"), date("2026-11-15")]);
+
+        schedule.last_posted = Some(date("2026-10-15"));
+
+        assert_eq!(schedule.forecast_dates(date("2026-10-18"), date("2026-11-17")), vec![date("2026-11-15")]);
+    }
+}
@@ -1,4 +1,6 @@
+use chrono::NaiveDate;
 use url_path::UrlPath;
+use crate::errors::CheckbookError;
 
 pub fn real_path(p: &str) -> String {
     if p.starts_with("~") {
@@ -6,4 +8,39 @@ pub fn real_path(p: &str) -> String {
     } else {
         UrlPath::new(p).normalize()
     }
-}
\ No newline at end of file
+}
+
+/// parse a month given on the command line, such as 2026-10, into the first day of that month.
+pub fn parse_month(m: &str) -> Result<NaiveDate, CheckbookError> {
+    NaiveDate::parse_from_str(&format!("{}-01", m), "%Y-%m-%d").map_err(|_| CheckbookError::Parse(format!("{} is not a valid month. Please use YYYY-MM.", m)))
+}
+
+/// parse a date given on the command line, which is expected to be in YYYY-MM-DD format.
+pub fn parse_date(d: &str) -> Result<NaiveDate, CheckbookError> {
+    NaiveDate::parse_from_str(d, qif::DateFormat::FullYearMonthDay.chrono_str()).map_err(|_| CheckbookError::Parse(format!("{} is not a valid date. Please use YYYY-MM-DD.", d)))
+}
+
+/** read text exported by a bank, which is not always UTF-8.
+ * Anything that is not valid UTF-8 is read as Latin-1, which older exports tend to use.
+ */
+pub fn decode_text(bytes: &[u8]) -> String {
+    String::from_utf8(bytes.to_vec()).unwrap_or_else(|_| bytes.iter().map(|byte| char::from(*byte)).collect())
+}
+/** implement Display for enums given on the command line, showing the name clap knows them by.
+ * Those names are also what gets stored in the database, so they are kept in one place.
+ */
+macro_rules! display_value_name {
+    ($($enumeration:ty),+) => {
+        $(
+            impl std::fmt::Display for $enumeration {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    let value = clap::ValueEnum::to_possible_value(self).expect("command line values are never skipped");
+
+                    write!(f, "{}", value.get_name())
+                }
+            }
+        )+
+    };
+}
+
+pub(crate) use display_value_name;
@@ -0,0 +1,14 @@
+use clap::ValueEnum;
+use crate::shared::display_value_name;
+
+/** Represents how a bank signs the amounts in a single amount column.
+ * Most bank accounts show withdrawals as negative,
+ * while credit cards often show charges as positive.
+ */
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum SignConvention {
+    DepositsPositive,
+    WithdrawalsPositive
+}
+
+display_value_name!(SignConvention);
@@ -0,0 +1,72 @@
+use std::{ fmt, str::FromStr };
+use fastnum::D64;
+use serde::{ Serialize, Deserialize };
+
+/** Represents a portion of an entry assigned to a single category.
+ * Like amounts given to the add command, 
+ * the amount is negative for withdrawals and positive for deposits.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Split {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    #[serde(with = "bcheck_decimal")]
+    pub amount: D64,
+
+    #[serde(default = "String::new", skip_serializing_if = "String::is_empty")]
+    pub memo: String
+}
+
+impl Split {
+    pub fn from(category: &str, amount: D64, memo: &str) -> Self {
+        Self {
+            category: if category.is_empty() || category.to_lowercase() == "uncategorized" {
+                None
+            } else {
+                Some(category.to_string())
+            },
+            amount,
+            memo: memo.to_string()
+        }
+    }
+}
+
+/// parse a split given in the form of Category=Amount or Category=Amount=Memo.
+impl FromStr for Split {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let components: Vec<&str> = s.splitn(3, '=').collect();
+
+        if components.len() < 2 {
+            return Err(format!("{} is not in the form of Category=Amount", s));
+        }
+
+        match components[1].trim().parse::<D64>() {
+            Ok(amount) => Ok(Split::from(components[0].trim(), amount, components.get(2).unwrap_or(&""))),
+            Err(_) => Err(format!("{} is not a valid amount", components[1]))
+        }
+    }
+}
+
+impl fmt::Display for Split {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\t{:.2}", self.category.clone().unwrap_or("Uncategorized".to_string()), self.memo, self.amount)
+    }
+}
+
+/// split amounts are written to bcheck files as numbers, like the amounts of entries.
+mod bcheck_decimal {
+    use fastnum::D64;
+    use serde::{ Deserialize, Deserializer, Serializer };
+
+    pub fn serialize<S>(decimal: &D64, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer, {
+        serializer.serialize_f64(decimal.to_f64())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<D64, D::Error> where D: Deserializer<'de>, {
+        let float_value = f64::deserialize(deserializer)?;
+        Ok(crate::amount::from_float(float_value))
+    }
+}
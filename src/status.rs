@@ -0,0 +1,73 @@
+use bcheck::Record;
+use clap::ValueEnum;
+use crate::shared::display_value_name;
+
+/** Represents how far an entry has come in matching up with the bank.
+ * Entries start out uncleared, are cleared once the bank has them,
+ * and are reconciled once they have been checked against a statement.
+ */
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum Status {
+    Uncleared,
+    Cleared,
+    Reconciled
+}
+
+impl Status {
+    /// retrieve the status implied by whether an entry is reconciled, for places that only know that much.
+    pub fn from_reconciled(is_reconciled: bool) -> Self {
+        if is_reconciled { Self::Reconciled } else { Self::Uncleared }
+    }
+
+    /** retrieve the status marked by the given letter.
+     * Y, R, X, and * mark reconciled entries, while C marks cleared ones,
+     * which covers both the ledger and the markers used by QIF files.
+     */
+    pub fn from_letter(letter: &str) -> Self {
+        match letter.trim().to_uppercase().as_str() {
+            "Y" | "R" | "X" | "*" => Self::Reconciled,
+            "C" => Self::Cleared,
+            _ => Self::Uncleared
+        }
+    }
+
+    /// retrieve the letter the status is shown with in listings, TSV files, and spreadsheets.
+    pub fn letter(&self) -> &'static str {
+        match self {
+            Self::Uncleared => "N",
+            Self::Cleared => "C",
+            Self::Reconciled => "Y"
+        }
+    }
+}
+
+/** retrieve the statuses to keep for the status filters given to list and export.
+ * -r keeps reconciled entries, while --not-reconciled keeps the rest,
+ * and no filter at all keeps everything.
+ */
+pub fn statuses_to_show(status: &Option<Status>, reconciled: bool, not_reconciled: bool) -> Vec<Status> {
+    if let Some(status) = status {
+        vec![status.clone()]
+    } else if reconciled {
+        vec![Status::Reconciled]
+    } else if not_reconciled {
+        vec![Status::Uncleared, Status::Cleared]
+    } else {
+        vec![]
+    }
+}
+
+display_value_name!(Status);
+
+/** present a record as a line of tab separated values, like a TSV export, with the given status.
+ * bcheck only knows whether an entry is reconciled, so the status column is filled in here.
+ */
+pub fn record_with_status(record: &Record, status: &Status) -> String {
+    let mut fields: Vec<String> = record.to_string().split('\t').map(String::from).collect();
+
+    if let Some(field) = fields.get_mut(3) {
+        *field = status.letter().to_string();
+    }
+
+    fields.join("\t")
+}
@@ -6,6 +6,16 @@ use crate::update::Update;
 use crate::import::Import;
 use crate::export::Export;
 use crate::summary::Summary;
+use crate::forecast::Forecast;
+use crate::budget::Budget;
+use crate::reconcile::Reconcile;
+use crate::account::Account;
+use crate::transfer::Transfer;
+use crate::category::Category;
+use crate::rules::Rules;
+use crate::schedule::Schedule;
+use crate::csv_profiles::CsvProfiles;
+use crate::db::Db;
 
 #[derive(Parser)]
 pub enum SubCommand {
@@ -15,5 +25,15 @@ pub enum SubCommand {
     List(List),
     Remove(Remove),
     Update(Update),
-    Summary(Summary)
+    Summary(Summary),
+    Forecast(Forecast),
+    Budget(Budget),
+    Reconcile(Reconcile),
+    Account(Account),
+    Transfer(Transfer),
+    Category(Category),
+    Rules(Rules),
+    Schedule(Schedule),
+    CsvProfiles(CsvProfiles),
+    Db(Db)
 }
\ No newline at end of file
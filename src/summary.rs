@@ -2,69 +2,81 @@ use bcheck::{ Record, TransactionType };
 use chrono::Duration;
 use chrono::{ Months, prelude::* };
 use clap::Parser;
+use crate::amount::signed_amount;
+use crate::category_path::*;
+use crate::monthly_budget::MonthlyBudget;
 use crate::date_range::DateRange;
 use crate::records::Records;
 use crate::database::*;
+use crate::errors::CheckbookError;
 use crate::period::*;
+use crate::shared::parse_month;
+use crate::split::Split;
+use crate::status::Status;
+use std::collections::HashMap;
 use fastnum::D64;
 
 #[derive(Parser)]
-#[clap(version = "0.3", author = "Bryce Campbell", long_about = "Get a summary of the ledger. \r\n\r\nTo get a summary of the ledger, you can do something like this: \r\n\r\nrcheckbook summary path/to/database -p half-year \r\n\r\nThis will give you a summary of everything all the way back to 6 months ago. \r\n\r\nOther options include: \r\n\r\n* week\r\n* month\r\n* year\r\n* quarter (3 months)\r\n* all\r\n\r\nThe last item is the default.")]
+#[clap(version = "0.3", author = "Bryce Campbell", long_about = "Get a summary of the ledger. \r\n\r\nTo get a summary of the ledger, you can do something like this: \r\n\r\nrcheckbook summary path/to/database -p half-year \r\n\r\nThis will give you a summary of everything all the way back to 6 months ago. \r\n\r\nOther options include: \r\n\r\n* week\r\n* month\r\n* year\r\n* quarter (3 months)\r\n* all\r\n\r\nThe last item is the default.\r\n\r\nSummaries cover every account combined, unless a single account is given with --account.\r\n\r\nTransfers between accounts are shown on their own line and are not counted as income or expenses.\r\n\r\nEntries that are split across categories count each split toward its own category.\r\n\r\nCategories nested with colons, such as Utilities:Electric, are indented under their parent, and the total of a parent includes everything nested under it.\r\n\r\nTo compare what was spent in each category with what was budgeted, use --budget. Budgets are set with the budget command for whole months, so periods are counted in calendar months up to the current one, and --month compares a single month instead, such as --month 2026-09.")]
 pub struct Summary {
     #[clap(default_value = "~/.checkbook/register.db")]
     pub file_path: String,
 
     #[clap(long, short, value_enum, default_value_t=Period::All)]
     pub period: Period,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long, short)]
+    pub budget: bool,
+
+    #[clap(long, requires = "budget", conflicts_with = "period")]
+    pub month: Option<String>
 }
 
 impl Summary {
-    pub fn run(&self) {
-        copy_database_if_not_exists(&self.file_path);
-        let record_store = Records::from(load_records_from_db(&self.file_path));
-        let categories = load_categories_from_db(&self.file_path);
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let db = copy_database_if_not_exists(&self.file_path)?;
+        let record_store = Records::from(load_ledger_from_db(&db, &self.account)?);
+        let categories = load_categories_from_db(&db)?;
+        let transfers = load_transfer_ids_from_db(&db)?;
+        let splits = load_splits_from_db(&db)?;
         let today = Local::now().date_naive();
 
-        match self.period {
-            Period::Week => {
-                let one_week_ago = today - Duration::weeks(1);
-                let week_records: Vec<Record> = record_store.sorted_records().into_iter().filter(|record| DateRange::from(one_week_ago, today).contains(record.transaction.date)).collect();
-
-                Self::display(&week_records, &categories, &self.period, &self.file_path);
-            },
-            Period::Month => {
-                let one_month_ago = today - Months::new(1);
-                let month_records: Vec<Record> = record_store.sorted_records().into_iter().filter(|record| DateRange::from(one_month_ago, today).contains(record.transaction.date)).collect();
-
-                Self::display(&month_records, &categories, &self.period, &self.file_path);
-            },
-            Period::Quarter => {
-                let three_months_ago = today - Months::new(3);
-                let quarter_records: Vec<Record> = record_store.sorted_records().into_iter().filter(|record| DateRange::from(three_months_ago, today).contains(record.transaction.date)).collect();
-
-                Self::display(&quarter_records, &categories, &self.period, &self.file_path);
-            },
-            Period::HalfYear => {
-                let six_months_ago = today - Months::new(6);
-                let half_year_records: Vec<Record> = record_store.sorted_records().into_iter().filter(|record| DateRange::from(six_months_ago, today).contains(record.transaction.date)).collect();
-
-                Self::display(&half_year_records, &categories, &self.period, &self.file_path);
-            },
-            Period::Year => {
-                let one_year_ago = today - Months::new(12);
-                let year_records: Vec<Record> = record_store.sorted_records().into_iter().filter(|record| DateRange::from(one_year_ago, today).contains(record.transaction.date)).collect();
-
-                Self::display(&year_records, &categories, &self.period, &self.file_path);
-            },
-            Period::All => Self::display(&record_store.sorted_records(), &categories, &self.period, &self.file_path)
+        let start = match self.period {
+            Period::Week => Some(today - Duration::weeks(1)),
+            Period::Month => Some(today - Months::new(1)),
+            Period::Quarter => Some(today - Months::new(3)),
+            Period::HalfYear => Some(today - Months::new(6)),
+            Period::Year => Some(today - Months::new(12)),
+            Period::All => None
+        };
+
+        let period_records: Vec<Record> = match start {
+            Some(start) => record_store.sorted_records().into_iter().filter(|record| DateRange::from(start, today).contains(record.transaction.date)).collect(),
+            None => record_store.sorted_records()
+        };
+
+        if self.budget {
+            let budgets = load_budgets_from_db(&db)?;
+            let records = record_store.sorted_records();
+            let first_date = records.first().map(|record| record.transaction.date).into_iter().chain(budgets.iter().map(|budget| budget.month)).min();
+            let months = self.budget_months(today, first_date)?;
+
+            Self::display_budget(&records, &transfers, &splits, &budgets, &self.budget_title(&months), &months, &self.account);
+        } else {
+            Self::display(&period_records, &categories, &transfers, &splits, &self.period, &record_store, &self.account);
         }
+
+        Ok(())
     }
 
-    fn create_string(records: &Vec<Record>, categories: &Vec<String>, period: &Period, path: &str) -> String {
+    fn create_string(records: &[Record], categories: &[String], transfers: &[String], splits: &HashMap<String, Vec<Split>>, period: &Period, store: &Records, account: &Option<String>) -> String {
         let mut report = String::new();
-        let mut filtered_categories: Vec<String> = categories.clone().into_iter().filter(|category| category.to_lowercase() != "Opening Balance".to_string().to_lowercase()).collect();
+        let mut filtered_categories: Vec<String> = categories.iter().filter(|category| category.to_lowercase() != "Opening Balance".to_string().to_lowercase()).cloned().collect();
         filtered_categories.push("Uncategorized".to_string());
-        filtered_categories.sort();
+        filtered_categories.sort_by_key(|category| sort_key(category));
 
         match period {
             Period::Week => report.push_str("WTD Report\r\n\r\n"),
@@ -75,84 +87,228 @@ impl Summary {
             Period::All => report.push_str("Summary\r\n\r\n")
         }
 
+        if let Some(account) = account {
+            let account_entry = format!("Account\t{}\r\n\r\n", account);
+            report.push_str(&account_entry);
+        }
+
         let opening_index = records.iter().position(|record| record.transaction.category.clone().unwrap_or("Uncategorized".to_string()).to_lowercase() == "Opening Balance".to_string().to_lowercase());
 
         let opening = format!("Opening Balance\t{:.2}\r\n\r\n", if let Some(starting_index) = opening_index {
-            retrieve_balance_for_record(path, records[starting_index].clone())
+            store.balance_for_record(&records[starting_index])
         } else if !records.is_empty() {
-            retrieve_balance_for_record(path, records[0].clone())
+            store.balance_for_record(&records[0])
         } else {
-            0.0
+            D64::ZERO
         });
 
         report.push_str(&opening);
 
+        let amounts = Self::category_amounts(records, transfers, splits);
+
         for category in filtered_categories{
-            let records_in_category: Vec<Record> = records.into_iter().filter(|record| record.transaction.category.clone().unwrap_or("Uncategorized".to_string()).to_lowercase() == category.to_lowercase()).map(|r| r.clone()).collect();
+            let entry = format!("{}{}\t{:.2}\r\n", "  ".repeat(depth(&category)), leaf_name(&category), Self::category_total(&amounts, &category));
 
-            let category_total = records_in_category.into_iter().fold(D64::from_f64(0.0), |sum, record| {
-                if let TransactionType::Withdrawal = record.transaction.transaction_type {
-                    sum - record.transaction.amount
-                } else {
-                    sum + record.transaction.amount
-                }
-            });
+            report.push_str(&entry);
+        }
 
-            let entry = format!("{}\t{:.2}\r\n", category, category_total);
+        let transfer_records: Vec<Record> = records.iter().filter(|record| transfers.contains(&record.id.to_lowercase())).cloned().collect();
 
-            report.push_str(&entry);
+        if !transfer_records.is_empty() {
+            let transfer_total = transfer_records.into_iter().fold(D64::ZERO, |sum, record| sum + signed_amount(&record.transaction));
+
+            let transfer_entry = format!("Transfers\t{:.2}\r\n", transfer_total);
+
+            report.push_str(&transfer_entry);
         }
 
         let last_record = records.last();
 
         let balance = if let Some(last) = last_record {
-            retrieve_balance_for_record(path, last.clone())
+            store.balance_for_record(last)
         } else {
-            0.0
+            D64::ZERO
         };
 
         let balance_entry = format!("\r\nBalance\t{:.2}\r\n\r\n", balance);
         report.push_str(&balance_entry);
 
-        let total_income = records.into_iter().filter(|r| r.transaction.transaction_type == TransactionType::Deposit).fold(0.0, |sum, i| sum + i.transaction.amount.to_f64());
+        let total_income = records.iter().filter(|r| r.transaction.transaction_type == TransactionType::Deposit && !transfers.contains(&r.id.to_lowercase())).fold(D64::ZERO, |sum, i| sum + i.transaction.amount);
 
         let income_entry = format!("Total Income\t{:.2}\r\n", total_income);
         report.push_str(&income_entry);
 
 
-        let total_expenses = records.into_iter().filter(|r| r.transaction.transaction_type == TransactionType::Withdrawal).fold(0.0, |sum, i| sum + i.transaction.amount.to_f64());
+        let total_expenses = records.iter().filter(|r| r.transaction.transaction_type == TransactionType::Withdrawal && !transfers.contains(&r.id.to_lowercase())).fold(D64::ZERO, |sum, i| sum + i.transaction.amount);
 
         let expenditure_entry = format!("Total Expenditures\t{:.2}\r\n\r\n", total_expenses);
         report.push_str(&expenditure_entry);
 
-        let total_reconciled = records.into_iter().filter(|r| r.transaction.is_reconciled).fold(0.0, |sum, i| {
-            if let TransactionType::Deposit = i.transaction.transaction_type {
-                sum + i.transaction.amount.to_f64()
-            } else {
-                sum - i.transaction.amount.to_f64()
-            }
-        });
+        let status_total = |status: Status| records.iter().filter(|r| store.status_for_record(r) == status).fold(D64::ZERO, |sum, i| sum + signed_amount(&i.transaction));
 
-        let reconciled_entry = format!("Reconciled\t{:.2}\r\n", total_reconciled);
+        let reconciled_entry = format!("Reconciled\t{:.2}\r\n", status_total(Status::Reconciled));
         report.push_str(&reconciled_entry);
 
-        let total_unreconciled = records.into_iter().filter(|r| !r.transaction.is_reconciled).fold(0.0, |sum, i| {
-            if let TransactionType::Deposit = i.transaction.transaction_type {
-                sum + i.transaction.amount.to_f64()
+        let cleared_entry = format!("Cleared\t{:.2}\r\n", status_total(Status::Cleared));
+        report.push_str(&cleared_entry);
+
+        let uncleared_entry = format!("Uncleared\t{:.2}", status_total(Status::Uncleared));
+        report.push_str(&uncleared_entry);
+
+
+
+        report
+    }
+
+    /** retrieve the category and amount of every entry that is not a transfer.
+     * Entries that are split across categories give one amount for each split.
+     */
+    fn category_amounts(records: &[Record], transfers: &[String], splits: &HashMap<String, Vec<Split>>) -> Vec<(String, D64)> {
+        records.iter().filter(|record| !transfers.contains(&record.id.to_lowercase())).flat_map(|record| if let Some(record_splits) = splits.get(&record.id.to_lowercase()) {
+            record_splits.iter().map(|split| (split.category.clone().unwrap_or("Uncategorized".to_string()), split.amount)).collect()
+        } else {
+            vec![(record.transaction.category.clone().unwrap_or("Uncategorized".to_string()), signed_amount(&record.transaction))]
+        }).collect()
+    }
+
+    /// retrieve the total of a category, including every category nested under it.
+    fn category_total(amounts: &[(String, D64)], category: &str) -> D64 {
+        amounts.iter().filter(|(amount_category, _)| is_within(amount_category, category)).fold(D64::ZERO, |sum, (_, amount)| sum + *amount)
+    }
+
+    /** retrieve the first day of each calendar month that a budget report covers, ending with the current month.
+     * Budgets are set for whole months, so periods are counted in calendar months,
+     * such as the months of this quarter so far, rather than going back a number of days.
+     * A single month can be given with --month instead.
+     */
+    fn budget_months(&self, today: NaiveDate, first_date: Option<NaiveDate>) -> Result<Vec<NaiveDate>, CheckbookError> {
+        let current_month = today.with_day(1).unwrap_or(today);
+
+        if let Some(month) = &self.month {
+            return Ok(vec![parse_month(month)?]);
+        }
+
+        let first_month = match self.period {
+            Period::Week => return Err(CheckbookError::InvalidInput("Budgets are set for whole months, so they cannot be compared over a week. Please use a period of a month or longer, or give one with --month.".to_string())),
+            Period::Month => current_month,
+            Period::Quarter => current_month - Months::new(today.month0() % 3),
+            Period::HalfYear => current_month - Months::new(5),
+            Period::Year => current_month - Months::new(today.month0()),
+            Period::All => first_date.and_then(|date| date.with_day(1)).unwrap_or(current_month).min(current_month)
+        };
+
+        let mut months = vec![];
+        let mut month = first_month;
+
+        while month <= current_month {
+            months.push(month);
+            month = month + Months::new(1);
+        }
+
+        Ok(months)
+    }
+
+    fn budget_title(&self, months: &[NaiveDate]) -> String {
+        if let (Some(_), Some(month)) = (&self.month, months.first()) {
+            return format!("{} Budget", month.format("%Y-%m"));
+        }
+
+        match self.period {
+            Period::Week => "WTD Budget",
+            Period::Month => "MTD Budget",
+            Period::Quarter => "QTD Budget",
+            Period::HalfYear => "6 Month Budget",
+            Period::Year => "YTD Budget",
+            Period::All => "Budget"
+        }.to_string()
+    }
+
+    /// create a report comparing what was budgeted for each category over the given months with what was actually spent in them.
+    fn create_budget_string(records: &[Record], transfers: &[String], splits: &HashMap<String, Vec<Split>>, budgets: &[MonthlyBudget], title: &str, months: &[NaiveDate], account: &Option<String>) -> String {
+        let mut report = format!("{}\r\n\r\n", title);
+
+        if let Some(account) = account {
+            let account_entry = format!("Account\t{}\r\n\r\n", account);
+            report.push_str(&account_entry);
+        }
+
+        let month_records: Vec<Record> = records.iter().filter(|record| months.iter().any(|month| record.transaction.date.year() == month.year() && record.transaction.date.month() == month.month())).cloned().collect();
+        let month_budgets: Vec<&MonthlyBudget> = budgets.iter().filter(|budget| months.iter().any(|month| budget.covers(*month))).collect();
+        let amounts = Self::category_amounts(&month_records, transfers, splits);
+
+        let mut budgeted_categories: Vec<String> = month_budgets.iter().map(|budget| budget.category.clone()).collect();
+        budgeted_categories.sort_by_key(|category| sort_key(category));
+        budgeted_categories.dedup_by_key(|category| category.to_lowercase());
+
+        report.push_str("Category\tBudgeted\tActual\tRemaining\tUsed\r\n");
+
+        for category in &budgeted_categories {
+            let budgeted = month_budgets.iter().filter(|budget| budget.category.to_lowercase() == category.to_lowercase()).fold(D64::ZERO, |sum, budget| sum + budget.amount);
+            let actual = D64::ZERO - Self::category_total(&amounts, category);
+
+            let used = if budgeted.is_zero() {
+                String::new()
             } else {
-                sum - i.transaction.amount.to_f64()
-            }
-        });
+                format!("{:.0}%", actual / budgeted * D64::from(100))
+            };
+
+            let entry = format!("{}{}\t{:.2}\t{:.2}\t{:.2}\t{}{}\r\n", "  ".repeat(depth(category)), leaf_name(category), budgeted, actual, budgeted - actual, used, if actual > budgeted { "\toverspent" } else { "" });
 
-        let unreconciled_entry = format!("Unreconciled\t{:.2}", total_unreconciled);
-        report.push_str(&unreconciled_entry);
+            report.push_str(&entry);
+        }
 
+        let unbudgeted = amounts.iter().filter(|(category, amount)| *amount < D64::ZERO && category.to_lowercase() != "opening balance" && !budgeted_categories.iter().any(|budgeted_category| is_within(category, budgeted_category))).fold(D64::ZERO, |sum, (_, amount)| sum - *amount);
 
+        let unbudgeted_entry = format!("\r\nUnbudgeted\t\t{:.2}", unbudgeted);
+        report.push_str(&unbudgeted_entry);
 
-        return report;
+        report
     }
 
-    fn display(records: &Vec<Record>, categories: &Vec<String>, period: &Period, path: &str) {
-        print!("{}", Self::create_string(records, categories, period, path));
+    fn display_budget(records: &[Record], transfers: &[String], splits: &HashMap<String, Vec<Split>>, budgets: &[MonthlyBudget], title: &str, months: &[NaiveDate], account: &Option<String>) {
+        print!("{}", Self::create_budget_string(records, transfers, splits, budgets, title, months, account));
+    }
+
+    fn display(records: &[Record], categories: &[String], transfers: &[String], splits: &HashMap<String, Vec<Split>>, period: &Period, store: &Records, account: &Option<String>) {
+        print!("{}", Self::create_string(records, categories, transfers, splits, period, store, account));
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bcheck::Transaction;
+    use crate::amount::set_signed_amount;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").expect("could not parse date")
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn counts_budget_months_by_calendar() {
+        let summary = |period: Period, month: Option<&str>| Summary { file_path: String::new(), period, account: None, budget: true, month: month.map(String::from) };
+        let today = date("2026-11-18");
+
+        assert_eq!(summary(Period::Month, None).budget_months(today, None).expect("could not count months"), vec![date("2026-11-01")]);
+        assert_eq!(summary(Period::Quarter, None).budget_months(today, None).expect("could not count months"), vec![date("2026-10-01"), date("2026-11-01")]);
+        assert_eq!(summary(Period::Year, None).budget_months(today, None).expect("could not count months").len(), 11);
+        assert_eq!(summary(Period::All, None).budget_months(today, Some(date("2026-09-30"))).expect("could not count months"), vec![date("2026-09-01"), date("2026-10-01"), date("2026-11-01")]);
+        assert_eq!(summary(Period::All, Some("2026-08")).budget_months(today, None).expect("could not count months"), vec![date("2026-08-01")]);
+        assert!(summary(Period::Week, None).budget_months(today, None).is_err());
+    }
+
+    #[test]
+    fn compares_whole_months_with_budgets() {
+        let records: Vec<Record> = [("A", "2026-08-31", "-50.00"), ("B", "2026-09-10", "-120.00"), ("C", "2026-10-02", "-300.00"), ("D", "2026-10-31", "-30.00")].into_iter().map(|(id, date, amount)| {
+            let mut transaction = Transaction::builder().set_date(date).set_vendor("Market").set_category("Groceries").build();
+            set_signed_amount(&mut transaction, amount.parse::<D64>().expect("could not parse amount"));
+
+            Record::from(id, transaction)
+        }).collect();
+
+        let budgets: Vec<MonthlyBudget> = ["2026-08-01", "2026-09-01", "2026-10-01"].into_iter().map(|month| MonthlyBudget::from("Groceries", date(month), D64::from(400))).collect();
+        let report = Summary::create_budget_string(&records, &[], &HashMap::new(), &budgets, "QTD Budget", &[date("2026-09-01"), date("2026-10-01")], &None);
+
+        // the budgets of both months count in full, along with everything spent in them.
+        assert!(report.contains("Groceries\t800.00\t450.00\t350.00\t56%\r\n"), "unexpected report {}", report);
+    }
+}
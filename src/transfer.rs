@@ -0,0 +1,83 @@
+use bcheck::{ Record, Transaction };
+use clap::Parser;
+use chrono::NaiveDate;
+use crate::{ amount::*, database::*, errors::CheckbookError };
+use fastnum::D64;
+use rusqlite::Connection;
+
+#[derive(Parser)]
+#[clap(version = "0.1", author = "Bryce Campbell", long_about = "Move money between accounts. \r\n\r\nA transfer can be made like this: \r\n\r\nrcheckbook transfer --from Default --to Savings -a 100.0 \r\n\r\nThis will add a withdrawal to the first account and a matching deposit to the second. \r\n\r\nThe two entries stay linked, so updating the date or amount of one will update the other, and removing one will remove both. \r\n\r\nTransfers are not counted as income or expenses in summaries.")]
+pub struct Transfer {
+
+    #[clap(default_value = "~/.checkbook/register.db")]
+    pub file_path: String,
+
+    #[clap(long, short)]
+    pub date: Option<String>,
+
+    #[clap(long, short)]
+    pub from: String,
+
+    #[clap(long, short)]
+    pub to: String,
+
+    #[clap(long, short, default_value = "")]
+    pub memo: String,
+
+    #[clap(long, short)]
+    pub amount: D64
+}
+
+impl Transfer {
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = copy_database_if_not_exists(&self.file_path)?;
+
+        if self.from.to_lowercase() == self.to.to_lowercase() {
+            Err(CheckbookError::InvalidInput("Please specify two different accounts.".to_string()))
+        } else if self.amount <= D64::ZERO {
+            Err(CheckbookError::InvalidInput("Please specify an amount greater than zero.".to_string()))
+        } else if let Some(date) = self.date.as_ref().filter(|date| NaiveDate::parse_from_str(date, qif::DateFormat::FullYearMonthDay.chrono_str()).is_err()) {
+            Err(CheckbookError::Parse(format!("{} is not a valid date. Please use YYYY-MM-DD.", date)))
+        } else {
+            let transaction = db.transaction()?;
+
+            self.add_transfer(&transaction)?;
+
+            transaction.commit()?;
+
+            Ok(())
+        }
+    }
+
+    fn add_transfer(&self, db: &Connection) -> Result<(), CheckbookError> {
+        let date = self.date.clone().unwrap_or_default();
+        let from = stored_account_name(db, &self.from)?;
+        let to = stored_account_name(db, &self.to)?;
+
+        let mut withdrawal = Transaction::builder()
+        .set_date(&date)
+        .set_vendor(&format!("Transfer to {}", to))
+        .set_memo(&self.memo)
+        .build();
+
+        set_signed_amount(&mut withdrawal, -self.amount);
+
+        let mut deposit = Transaction::builder()
+        .set_date(&date)
+        .set_vendor(&format!("Transfer from {}", from))
+        .set_memo(&self.memo)
+        .build();
+
+        set_signed_amount(&mut deposit, self.amount);
+
+        add_transfer_to_db(db, &Record::from("", withdrawal), &self.from, &Record::from("", deposit), &self.to)
+    }
+}
+
+/// retrieve the name of an account as it is stored, falling back to the given name.
+fn stored_account_name(db: &Connection, a: &str) -> Result<String, CheckbookError> {
+    Ok(load_accounts_from_db(db)?.into_iter()
+    .find(|account| account.name.to_lowercase() == a.to_lowercase())
+    .map(|account| account.name)
+    .unwrap_or(a.to_string()))
+}
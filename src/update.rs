@@ -1,11 +1,12 @@
-use bcheck::TransactionType;
+use bcheck::{ Record, TransactionType };
 use chrono::NaiveDate;
-use crate::database::*;
+use crate::{ amount::*, database::*, errors::CheckbookError, split::Split, status::Status };
 use clap::Parser;
 use fastnum::D64;
+use rusqlite::Connection;
 
 #[derive(Parser)]
-#[clap(version = "0.4.1", author = "Bryce Campbell", long_about = "Update an existing entry. \r\n\r\nUpdating entries in the checkbook can be done like this: \r\n\r\nrcheckbook update -i FF04C3DC-F0FE-472E-8737-0F4034C049F0 -r \r\n\r\nThis will mark the transaction with the given identifier as reconciled. \r\n\r\nNearly anything can be updated, including whether the transaction is a deposit or not with the -t flag. \r\n\r\nIf you want to correct a date, that is done with the --date flag and expects the date to be in YYYY-MM-DD format.")]
+#[clap(version = "0.4.1", author = "Bryce Campbell", long_about = "Update an existing entry. \r\n\r\nUpdating entries in the checkbook can be done like this: \r\n\r\nrcheckbook update -i FF04C3DC-F0FE-472E-8737-0F4034C049F0 -r \r\n\r\nThis will mark the transaction with the given identifier as reconciled. \r\n\r\nTo mark a transaction as cleared, meaning the bank has it but it has not been reconciled yet, use -s cleared instead. -s can also set a transaction back to uncleared. \r\n\r\nNearly anything can be updated, including whether the transaction is a deposit or not with the -t flag. \r\n\r\nIf you want to correct a date, that is done with the --date flag and expects the date to be in YYYY-MM-DD format.\r\n\r\nEntries can be moved to another account with --account.\r\n\r\nGiving --split will replace the splits of an entry, while giving --category will replace its splits with the category. The splits must add up to the amount of the entry.\r\n\r\nEntries on the same day are kept in the order they were added. To move an entry to the top of its day, do this: \r\n\r\nrcheckbook update -i FF04C3DC-F0FE-472E-8737-0F4034C049F0 --position 1")]
 pub struct Update {
     #[clap(default_value = "~/.checkbook/register.db")]
     pub file_path: String,
@@ -29,7 +30,7 @@ pub struct Update {
     pub memo: Option<String>,
 
     #[clap(long, short)]
-    pub amount: Option<f64>,
+    pub amount: Option<D64>,
 
     #[clap(long, short)]
     pub transaction_type: Option<TransactionType>,
@@ -38,58 +39,120 @@ pub struct Update {
     pub reconciled: bool,
 
     #[clap(long)]
-    pub not_reconciled: bool
+    pub not_reconciled: bool,
+
+    #[clap(long, short, value_enum, conflicts_with_all = ["reconciled", "not_reconciled"])]
+    pub status: Option<Status>,
+
+    #[clap(long)]
+    pub account: Option<String>,
+
+    #[clap(long = "split")]
+    pub splits: Vec<Split>,
+
+    #[clap(long)]
+    pub position: Option<usize>
 }
 
 impl Update {
-    pub fn run(&self) {
-        self.update_record(&self.file_path);
+    pub fn run(&self) -> Result<(), CheckbookError> {
+        let mut db = open_database(&self.file_path)?;
+        let transaction = db.transaction()?;
+
+        self.update_record(&transaction)?;
+
+        transaction.commit()?;
+
+        Ok(())
     }
 
-    fn update_record(&self, p: &str) {
+    fn update_record(&self, db: &Connection) -> Result<(), CheckbookError> {
+        let Some(mut stored_record) = retrieve_record_with_id_from_db(db, &self.id)? else {
+            return Err(CheckbookError::RecordNotFound(self.id.clone()));
+        };
 
-        if let Some(mut stored_record) = retrieve_record_with_id_from_db(p, &self.id) {
-            if let Some(date) = &self.date {
-                if let Ok(naive_date) = NaiveDate::parse_from_str(date, qif::DateFormat::FullYearMonthDay.chrono_str()) {
-                    stored_record.transaction.date = naive_date
-                }
-            }
+        if self.position == Some(0) {
+            return Err(CheckbookError::InvalidInput("Please specify a position of 1 or more.".to_string()));
+        }
 
-            if let Some(check_number) = self.check_number {
-                stored_record.transaction.check_number = if check_number > 0 { Some(check_number) } else { None };
+        if let Some(date) = &self.date {
+            match NaiveDate::parse_from_str(date, qif::DateFormat::FullYearMonthDay.chrono_str()) {
+                Ok(naive_date) => stored_record.transaction.date = naive_date,
+                Err(_) => return Err(CheckbookError::Parse(format!("{} is not a valid date. Please use YYYY-MM-DD.", date)))
             }
+        }
 
-            if let Some(category) = &self.category {
-                stored_record.transaction.category = if category.is_empty() { 
-                    None 
-                } else {
-                    Some(category.to_owned())
-                };
-            }
+        if let Some(check_number) = self.check_number {
+            stored_record.transaction.check_number = if check_number > 0 { Some(check_number) } else { None };
+        }
 
-            if let Some(vendor) = &self.vendor {
-                stored_record.transaction.vendor = vendor.to_owned();
-            }
+        if let Some(category) = &self.category {
+            stored_record.transaction.category = if category.is_empty() { 
+                None 
+            } else {
+                Some(category.to_owned())
+            };
+        }
 
-            if let Some(memo) = &self.memo {
-                stored_record.transaction.memo = memo.to_owned();
-            }
+        if let Some(vendor) = &self.vendor {
+            stored_record.transaction.vendor = vendor.to_owned();
+        }
 
-            if let Some(amount) = self.amount {
-                stored_record.transaction.amount = D64::from_f64(amount);
-            }
+        if let Some(memo) = &self.memo {
+            stored_record.transaction.memo = memo.to_owned();
+        }
 
-            if let Some(transaction_type) = self.transaction_type.clone() {
-                stored_record.transaction.transaction_type = transaction_type;
-            }
+        if let Some(amount) = self.amount {
+            stored_record.transaction.amount = amount;
+        }
+
+        if let Some(transaction_type) = self.transaction_type.clone() {
+            stored_record.transaction.transaction_type = transaction_type;
+        }
+
+        if self.reconciled {
+            stored_record.transaction.is_reconciled = true
+        } else if self.not_reconciled {
+            stored_record.transaction.is_reconciled = false
+        }
+
+        let stored_splits = load_splits_from_db(db)?.remove(&stored_record.id.to_lowercase()).unwrap_or(vec![]);
+
+        if !self.splits.is_empty() && self.category.is_some() {
+            return Err(CheckbookError::InvalidInput("Please use either a category or splits, not both.".to_string()));
+        } else if !self.splits.is_empty() && !splits_match_record(&self.splits, &stored_record) {
+            return Err(CheckbookError::InvalidInput("The splits do not add up to the amount of the entry.".to_string()));
+        } else if self.splits.is_empty() && self.category.is_none() && !stored_splits.is_empty() && !splits_match_record(&stored_splits, &stored_record) {
+            return Err(CheckbookError::InvalidInput("The amount no longer matches the splits. Please provide the splits again with --split.".to_string()));
+        }
+
+        update_record_in_db(db, &stored_record)?;
 
-            if self.reconciled {
-                stored_record.transaction.is_reconciled = true
-            } else if self.not_reconciled {
-                stored_record.transaction.is_reconciled = false
-            } else {}
+        if let Some(status) = &self.status {
+            set_record_status_in_db(db, &stored_record.id, status)?;
+        }
+
+        if !self.splits.is_empty() {
+            replace_splits_in_db(db, &stored_record.id, &self.splits)?;
+        } else if self.category.is_some() && !stored_splits.is_empty() {
+            replace_splits_in_db(db, &stored_record.id, &vec![])?;
+        }
+
+        if let Some(account) = &self.account {
+            move_record_to_account(db, &stored_record.id, account)?;
+        }
 
-            update_record_in_db(p, &stored_record);
+        if let Some(position) = self.position {
+            move_record_within_day(db, &stored_record.id, position)?;
         }
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// check if the splits add up to the amount of the given record.
+fn splits_match_record(splits: &[Split], record: &Record) -> bool {
+    let split_total = splits.iter().fold(D64::ZERO, |sum, split| sum + split.amount);
+
+    split_total.round(2) == signed_amount(&record.transaction).round(2)
+}